@@ -1,5 +1,20 @@
-use std::backtrace;
-use std::sync::Mutex;
+#[cfg(feature = "indent")]
+use std::cell::{Cell, RefCell};
+#[cfg(feature = "indent")]
+use std::collections::VecDeque;
+#[cfg(feature = "indent")]
+use std::io;
+#[cfg(feature = "indent")]
+use std::io::IsTerminal;
+#[cfg(feature = "indent")]
+use std::panic::Location;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+#[cfg(feature = "indent")]
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+#[cfg(feature = "indent")]
+use std::time::{Duration, Instant};
 
 /// Prints to the standard output, indented by the size of the local call stack.
 /// 
@@ -7,22 +22,55 @@ use std::sync::Mutex;
 /// its relation to the previous `trace!` call, and the first frame in the call
 /// stack that exists in the same crate as a past `trace!` caller.
 /// 
-/// Note that this macro utilizes [`std::backtrace`] which may be performance
-/// intensive and inconsistent, especially across platforms. Currently, `trace!`
-/// also blocks threads, as the Debug implementation of Backtrace is blocking.
+/// Note that this macro captures the call stack using the [`backtrace`] crate,
+/// which may be performance intensive and inconsistent, especially across
+/// platforms. Currently, `trace!` also blocks threads, as capturing and
+/// resolving the call stack is blocking, even while printing from a background
+/// thread (see `set_background`).
+/// 
+/// Each line is written while holding the lock of the standard output, so
+/// lines traced from different threads (or printed by [`println!`]) never
+/// interleave partway through a line.
+/// 
+/// Every macro enters this crate through a function that's never inlined, and
+/// no frames from there on count towards the depth, so the same call tree is
+/// indented the same in debug and release builds. Without debug info, though,
+/// the caller's own inlined functions may be missing from the call stack.
 /// 
 /// This macro is fully equivalent to [`println!`] if the `RUST_BACKTRACE` or
 /// `RUST_LIB_BACKTRACE` environment variables are both not set (or if the
 /// call stack otherwise couldn't be captured), avoiding the performance cost.
+/// See [`force_capture`] to capture it regardless.
+/// 
+/// When given an expression instead of a format string, `trace!` behaves like
+/// [`dbg!`]: it prints the expression's source and [`Debug`] representation,
+/// then returns the value by ownership (pass a reference, like `trace!(&x)`, to
+/// avoid moving it). Multiple expressions are returned as a tuple. A lone
+/// literal argument is always treated as a format string.
+/// 
+/// If the `indent` feature (enabled by default through `trace`) is disabled,
+/// this macro expands to nothing and its arguments are not evaluated, so any
+/// side effects they have are dropped. The expression form still evaluates and
+/// returns its value.
 /// 
+/// Enabling `indent` without `trace` keeps the macros and indentation without
+/// depending on the `backtrace` crate, for targets where the call stack can't
+/// be captured. Traces are then indented only by [`trace_at!`] or the
+/// call stacks given to [`set_frame_provider`], and otherwise printed as is.
+/// 
+/// [`backtrace`]: https://docs.rs/backtrace
+/// [`trace_at!`]: crate::trace_at
 /// [`println!`]: std::println
+/// [`dbg!`]: std::dbg
+/// [`Debug`]: std::fmt::Debug
 /// 
 /// # Panics
 ///
-/// Panics if writing to [`std::io::stdout`] fails.
+/// Panics if writing to [`std::io::stdout`] fails, or to whichever destination
+/// replaces it (like a [`set_default_writer`] writer), naming it in the message.
 ///
 /// Writing to non-blocking stdout can cause an error, which will lead
-/// this macro to panic.
+/// this macro to panic. Use [`try_trace!`] to handle the error instead.
 /// 
 /// # Indentation Symbols
 /// 
@@ -34,6 +82,20 @@ use std::sync::Mutex;
 /// - `@   ` or `@---` marks the baseline depth, like a main function or thread.
 /// - `|   ` marks the current depth relative to the baseline.
 /// 
+/// The baseline is the first function in the call stack that's in the same
+/// crate as the caller, or the crate set by [`set_baseline_crate`]. A trace
+/// from the baseline itself, like from `main` or a `#[test]` function, is a
+/// root and is printed as `@   ` with no further indentation. So is a trace
+/// with no function of its crate in the call stack.
+/// 
+/// These symbols and their width can be customized with [`set_config`].
+/// 
+/// With the `color` feature enabled, the `@` and `>---` markers are colored
+/// when printing to a terminal, unless the [`NO_COLOR`] environment variable
+/// is set. The message itself is never colored. See [`set_color_mode`].
+/// 
+/// [`NO_COLOR`]: https://no-color.org/
+/// 
 /// # Examples
 /// 
 /// ```
@@ -51,8 +113,21 @@ use std::sync::Mutex;
 ///     s(n-1, k-1) + s(n-1, k)*k
 /// }
 /// 
+/// # let lines = trace::capture_session(|| {
 /// trace!("# of ways to group 3 items into 2 unordered sets:");
 /// trace!("Result: {}", s(3, 2));
+/// # });
+/// # if cfg!(feature = "trace") {
+/// #     assert_eq!(lines, [
+/// #         "@---|   # of ways to group 3 items into 2 unordered sets:",
+/// #         "    >---|   n:3, k:2",
+/// #         "        >---|   n:2, k:1",
+/// #         "            >---|   n:1, k:0",
+/// #         "                |   n:1, k:1",
+/// #         "            |   n:2, k:2",
+/// #         "    |   Result: 3",
+/// #     ]);
+/// # }
 /// ```
 /// Output:
 /// ```text
@@ -64,101 +139,6926 @@ use std::sync::Mutex;
 ///             |   n:2, k:2
 ///     |   Result: 3
 /// ```
+/// 
+/// Tracing a value inline:
+/// 
+/// ```
+/// use trace::trace;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// let y = trace!(2 + 2);
+/// assert_eq!(y, 4);
+/// ```
+/// Output:
+/// ```text
+/// @---|   2 + 2 = 4
+/// ```
+/// 
+/// Tracing a reference returns the same reference, so a borrowed value can be
+/// traced without moving it:
+/// 
+/// ```
+/// use trace::trace;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// let names = vec!["a".to_owned(), "b".to_owned()];
+/// let count = trace!(&names).len();
+/// assert_eq!(count, 2);
+/// assert_eq!(names[0], "a");
+/// ```
+/// 
+/// Disabling the `indent` feature skips evaluation of the arguments entirely:
+/// 
+/// ```
+/// use trace::{trace, trace_to};
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// let mut evaluated = false;
+/// let mut out: Vec<u8> = Vec::new();
+/// trace_to!(&mut out, "{}", { evaluated = true; "side effect" }).unwrap();
+/// assert_eq!(evaluated, cfg!(feature = "indent"));
+/// assert_eq!(out.is_empty(), !cfg!(feature = "indent"));
+/// 
+/// // The expression form is always evaluated:
+/// assert_eq!(trace!(1 + 1), 2);
+/// ```
+/// 
+/// Each thread indents relative to its own call stack:
+/// 
+/// ```
+/// use trace::trace;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// fn count_down(n: u8) {
+///     trace!("n:{n}");
+///     if n > 0 {
+///         count_down(n - 1);
+///     }
+/// }
+/// 
+/// let threads: Vec<_> = (0..4)
+///     .map(|_| std::thread::spawn(|| count_down(3)))
+///     .collect();
+/// 
+/// for thread in threads {
+///     thread.join().unwrap();
+/// }
+/// ```
+/// 
+/// With no arguments, `trace!()` prints a depth ruler: the indentation alone,
+/// with an empty message, as a lightweight marker that a point was reached.
+/// It's compared to like any other trace, so the next line is indented
+/// relative to it:
+/// 
+/// ```
+/// use trace::trace;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// fn step() {
+///     trace!();
+/// }
+/// 
+/// let lines = trace::capture_session(|| {
+///     trace!("start");
+///     step();
+///     trace!("done");
+/// });
+/// 
+/// # if cfg!(feature = "trace") {
+/// assert_eq!(lines, ["@---|   start", "    >---|   ", "    |   done"]);
+/// # }
+/// ```
 #[macro_export]
 macro_rules! trace {
 	() => {
 		$crate::trace!("")
 	};
-	($($arg:tt)*) => {{
-		$crate::_trace(format!($($arg)*), module_path!());
-	}};
+	($fmt:literal $(, $($arg:tt)*)?) => {
+		$crate::_cfg_trace!({
+			if $crate::_is_traced(module_path!()) {
+				$crate::_trace(format!($fmt $(, $($arg)*)?), module_path!());
+			}
+		} else {})
+	};
+	($val:expr $(,)?) => {
+		$crate::_cfg_trace!({
+			match $val {
+				tmp => {
+					if $crate::_is_traced(module_path!()) {
+						$crate::_trace(
+							format!("{} = {:#?}", stringify!($val), &tmp),
+							module_path!()
+						);
+					}
+					tmp
+				}
+			}
+		} else {
+			$val
+		})
+	};
+	($($val:expr),+ $(,)?) => {
+		($($crate::trace!($val)),+,)
+	};
 }
 
-#[doc(hidden)]
-pub fn _trace(text: String, module_path: &str) {
-	//! Utility function for the [`trace!`] macro.
-	//! 
-	//! [`trace!`]: crate::trace
-	
-	let trace_capture = backtrace::Backtrace::capture();
-	if trace_capture.status() != backtrace::BacktraceStatus::Captured {
-		println!("{text}");
-		return
-	}
-	
-	static LAST_TRACE: Mutex<(Vec<String>, usize)> = Mutex::new((vec![], 0));
-	let (last_trace, basis_depth) = &mut *LAST_TRACE.lock().unwrap();
-	let last_trace_depth = last_trace.len();
-	
-	let trace_string = format!("{:#?}", trace_capture);
-	let trace        = trace_string.rsplit('\n');
-	let trace_path   = &format!("fn: \"{}::_trace\"", module_path!());
-	
-	let (min_depth, max_depth) = trace.size_hint();
-	last_trace.reserve(max_depth.unwrap_or(min_depth)
-		.saturating_sub(last_trace.capacity()));
-	
-	let crate_name = module_path.split("::").next().unwrap();
-	let crate_path = &format!("fn: \"{}::", crate_name);
-	
-	let mut trace_depth = 0;
-	let mut match_depth = 0;
-	let mut crate_depth = 0;
-	
-	'find_depth: for frame in trace {
-		if frame.contains(trace_path) {
-			break 'find_depth
-		}
-		
-		 // First Frame in Caller's Crate:
-		if crate_depth == 0 && frame.contains(crate_path) {
-			crate_depth = trace_depth;
-		}
-		
-		 // Compare & Update Stored Frame:
-		if trace_depth < last_trace_depth {
-			if match_depth == trace_depth && frame == last_trace[trace_depth] {
-				match_depth += 1;
+/// Prints to the standard output, indented by the size of the local call stack,
+/// returning any error.
+/// 
+/// Equivalent to the [`trace!`] macro, except that a [`TraceError`] is
+/// returned rather than panicking if writing to [`std::io::stdout`] fails.
+/// See [`TraceError`] for how each failure is reported.
+/// 
+/// [`trace!`]: crate::trace
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::{trace_to, try_trace};
+/// use std::io;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// try_trace!("Hello").unwrap();
+/// 
+/// // `trace_to!` similarly returns errors from its writer:
+/// struct BrokenPipe;
+/// 
+/// impl io::Write for BrokenPipe {
+///     fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+///         Err(io::ErrorKind::BrokenPipe.into())
+///     }
+///     fn flush(&mut self) -> io::Result<()> {
+///         Ok(())
+///     }
+/// }
+/// 
+/// let result = trace_to!(&mut BrokenPipe, "Hello");
+/// # if cfg!(feature = "trace") {
+/// assert_eq!(result.unwrap_err().kind(), io::ErrorKind::BrokenPipe);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! try_trace {
+	() => {
+		$crate::try_trace!("")
+	};
+	($($arg:tt)*) => {
+		$crate::_cfg_trace!({
+			if $crate::_is_traced(module_path!()) {
+				$crate::_try_trace(format!($($arg)*), module_path!())
+			} else {
+				::core::result::Result::<(), $crate::TraceError>::Ok(())
 			}
-			last_trace[trace_depth] = frame.to_owned();
 		} else {
-			last_trace.push(frame.to_owned());
+			::core::result::Result::<(), $crate::TraceError>::Ok(())
+		})
+	};
+}
+
+/// Prints to the standard output like [`trace!`], but only the first time this
+/// call site is reached.
+/// 
+/// Each invocation of `trace_once!` has its own flag, shared by all threads, so
+/// a trace in a hot loop is printed once, indented for that first call. A call
+/// made while tracing is disabled or filtered out doesn't use up the flag.
+/// 
+/// [`trace!`]: crate::trace
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace_once;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// let lines = trace::capture_session(|| {
+///     for i in 0..100 {
+///         trace::set_enabled(i != 0);
+///         trace_once!("first i:{i}");
+///         trace_once!("also once");
+///     }
+/// });
+/// 
+/// # if cfg!(feature = "trace") {
+/// assert_eq!(lines, ["@---|   first i:1", "    |   also once"]);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! trace_once {
+	() => {
+		$crate::trace_once!("")
+	};
+	($($arg:tt)+) => {
+		$crate::_cfg_trace!({
+			static ONCE: ::std::sync::atomic::AtomicBool
+				= ::std::sync::atomic::AtomicBool::new(false);
+			if $crate::_is_traced(module_path!())
+				&& !ONCE.swap(true, ::std::sync::atomic::Ordering::Relaxed)
+			{
+				$crate::_trace(format!($($arg)+), module_path!());
+			}
+		} else {})
+	};
+}
+
+/// Prints to the standard output like [`trace!`], but only if the condition is
+/// true.
+/// 
+/// The condition is evaluated first, so if it's false the message isn't
+/// formatted (nor are its arguments evaluated) and the call stack isn't
+/// captured.
+/// 
+/// [`trace!`]: crate::trace
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace_if;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// let lines = trace::capture_session(|| {
+///     for (x, verbose) in [(1, false), (2, true)] {
+///         trace_if!(verbose, "x={x}");
+///     }
+/// });
+/// 
+/// # if cfg!(feature = "trace") {
+/// assert_eq!(lines, ["@---|   x=2"]);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! trace_if {
+	($cond:expr, $($arg:tt)*) => {
+		$crate::_cfg_trace!({
+			if $cond {
+				$crate::trace!($($arg)*);
+			}
+		} else {})
+	};
+}
+
+/// Prints pre-built [`fmt::Arguments`] to the standard output like [`trace!`].
+/// 
+/// This is for wrapping `trace!` in other macros that already produce
+/// arguments with [`format_args!`]. The arguments are only formatted into a
+/// string if the trace is enabled and not filtered.
+/// 
+/// [`fmt::Arguments`]: std::fmt::Arguments
+/// [`trace!`]: crate::trace
+/// [`format_args!`]: std::format_args
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace_fmt;
+/// use std::fmt;
+/// use std::sync::atomic::{AtomicBool, Ordering};
+/// 
+/// static IS_FORMATTED: AtomicBool = AtomicBool::new(false);
+/// 
+/// struct Expensive;
+/// 
+/// impl fmt::Display for Expensive {
+///     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+///         IS_FORMATTED.store(true, Ordering::Relaxed);
+///         f.write_str("expensive")
+///     }
+/// }
+/// 
+/// macro_rules! my_log {
+///     ($($arg:tt)*) => {
+///         trace_fmt!(format_args!($($arg)*))
+///     };
+/// }
+/// 
+/// trace::set_enabled(false);
+/// my_log!("value: {}", Expensive);
+/// assert!(!IS_FORMATTED.load(Ordering::Relaxed));
+/// 
+/// trace::set_enabled(true);
+/// my_log!("value: {}", Expensive);
+/// # if cfg!(feature = "trace") {
+/// assert!(IS_FORMATTED.load(Ordering::Relaxed));
+/// # }
+/// ```
+#[macro_export]
+macro_rules! trace_fmt {
+	($args:expr $(,)?) => {
+		$crate::_cfg_trace!({
+			$crate::_trace_fmt($args, module_path!());
+		} else {})
+	};
+}
+
+/// Prints to the standard output like [`trace!`], but indented by the given
+/// depth instead of the call stack.
+/// 
+/// The call stack is never captured, so this works without `RUST_BACKTRACE`
+/// and is about as cheap as [`println!`], for code that tracks its own depth
+/// (like an interpreter). The depth is a `usize`, where 0 is unindented, and
+/// each level is drawn as a matched depth. Traces made by `trace_at!` don't
+/// affect how [`trace!`] compares call stacks, and vice versa. It's indented
+/// even without the `backtrace` crate, with only the `indent` feature enabled
+/// (see [`trace!`]).
+/// 
+/// [`trace!`]: crate::trace
+/// [`println!`]: std::println
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace_at;
+/// 
+/// let lines = trace::capture_session(|| {
+///     for depth in [0, 1, 2, 1] {
+///         trace_at!(depth, "depth:{depth}");
+///     }
+/// });
+/// 
+/// # if cfg!(feature = "indent") {
+/// assert_eq!(lines, [
+///     "|   depth:0",
+///     "    |   depth:1",
+///     "        |   depth:2",
+///     "    |   depth:1",
+/// ]);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! trace_at {
+	($depth:expr, $($arg:tt)+) => {
+		$crate::_cfg_trace!({
+			let depth: usize = $depth;
+			if $crate::_is_traced(module_path!()) {
+				$crate::_trace_at(depth, format!($($arg)+), module_path!());
+			}
+		} else {})
+	};
+}
+
+/// Prints to the standard output like [`trace_at!`], at the root of a new
+/// [`TraceCtx`], and returns the context.
+/// 
+/// Async tasks don't share a call stack with the code that spawned them, so
+/// their depth can't be derived from it across an `.await`. A context can be
+/// moved into a task instead, where [`trace_child!`] traces one level under it.
+/// Like [`trace_at!`], the call stack is never captured.
+/// 
+/// [`trace_at!`]: crate::trace_at
+/// [`trace_child!`]: crate::trace_child
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::{trace_child, trace_ctx};
+/// 
+/// let lines = trace::capture_session(|| {
+///     let ctx = trace_ctx!("root");
+///     for task in 0..2 {
+///         std::thread::spawn(move || {
+///             let task_ctx = trace_child!(ctx, "task {task}");
+///             trace_child!(task_ctx, "step of task {task}");
+///             assert_eq!(task_ctx.depth(), 1);
+///             assert_ne!(task_ctx.id(), ctx.id());
+///         }).join().unwrap();
+///     }
+/// });
+/// 
+/// # if cfg!(feature = "indent") {
+/// assert_eq!(lines, [
+///     "|   root",
+///     "    |   task 0",
+///     "        |   step of task 0",
+///     "    |   task 1",
+///     "        |   step of task 1",
+/// ]);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! trace_ctx {
+	($($arg:tt)+) => {
+		match $crate::TraceCtx::new() {
+			ctx => {
+				$crate::_cfg_trace!({
+					if $crate::_is_traced(module_path!()) {
+						$crate::_trace_at(ctx.depth(), format!($($arg)+), module_path!());
+					}
+				} else {});
+				ctx
+			}
 		}
-		
-		trace_depth += 1;
-	}
-	if trace_depth == 0 {
-		println!("{text}");
-		return
-	}
-	trace_depth -= 1;
-	last_trace.truncate(trace_depth);
-	match_depth = match_depth.min(trace_depth);
-	
-	 // Print Line w/ Indentation:
-	let mut depth_text = String::new();
-	if match_depth == 0 || match_depth < *basis_depth {
-		*basis_depth = if crate_depth == 0 {
-			trace_depth
-		} else {
-			crate_depth
-		};
-		if trace_depth > *basis_depth {
-			depth_text += "@---";
-			depth_text += &">---".repeat(trace_depth - *basis_depth - 1);
-			depth_text += "|   ";
-		} else {
-			depth_text += "@   ";
+	};
+}
+
+/// Prints to the standard output like [`trace_at!`], one level under the given
+/// [`TraceCtx`], and returns a context for that level. See [`trace_ctx!`].
+/// 
+/// [`trace_at!`]: crate::trace_at
+/// [`trace_ctx!`]: crate::trace_ctx
+#[macro_export]
+macro_rules! trace_child {
+	($ctx:expr, $($arg:tt)+) => {
+		match $crate::TraceCtx::child($ctx) {
+			ctx => {
+				$crate::_cfg_trace!({
+					if $crate::_is_traced(module_path!()) {
+						$crate::_trace_at(ctx.depth(), format!($($arg)+), module_path!());
+					}
+				} else {});
+				ctx
+			}
 		}
-	} else {
-		depth_text += &"    ".repeat(match_depth - *basis_depth);
-		depth_text += &">---".repeat(trace_depth - match_depth);
-		depth_text += "|   ";
-	}
-	depth_text += & if text.contains('\n') {
-		text.replace('\n', &format!("\n{}|   ", "    ".repeat(trace_depth - *basis_depth)))
-	} else {
-		text
 	};
-	println!("{depth_text}");
-}
\ No newline at end of file
+}
+
+/// Checks that a condition is true, printing an indented failure line like
+/// [`trace!`] if it isn't, so that the line shows where in the call tree the
+/// check failed.
+/// 
+/// After printing, this panics with the same message, unless disabled by
+/// [`set_assert_panics`]. An optional message with format arguments can follow
+/// the condition, like [`assert!`]. If the `indent` feature is disabled, the
+/// condition is still checked, but nothing is printed.
+/// 
+/// [`trace!`]: crate::trace
+/// [`set_assert_panics`]: crate::set_assert_panics
+/// [`assert!`]: std::assert
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::{trace, trace_assert};
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// fn count_down(n: i8) {
+///     trace!("n:{n}");
+///     trace_assert!(n != 0, "reached n:{n}");
+///     if n > -1 {
+///         count_down(n - 1);
+///     }
+/// }
+/// 
+/// let lines = trace::capture_session(|| {
+///     trace::set_assert_panics(false);
+///     count_down(1);
+/// });
+/// 
+/// # if cfg!(feature = "trace") {
+/// assert_eq!(lines, [
+///     "@--->---|   n:1",
+///     "        >---|   n:0",
+///     "            |   assertion failed: n != 0: reached n:0",
+///     "            >---|   n:-1",
+/// ]);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! trace_assert {
+	($cond:expr $(,)?) => {
+		$crate::trace_assert!($cond, "")
+	};
+	($cond:expr, $($arg:tt)+) => {
+		if !$cond {
+			let message = format!($($arg)+);
+			let message = if message.is_empty() {
+				concat!("assertion failed: ", stringify!($cond)).to_owned()
+			} else {
+				format!(concat!("assertion failed: ", stringify!($cond), ": {}"), message)
+			};
+			$crate::_cfg_trace!({
+				$crate::trace!("{message}");
+			} else {});
+			if $crate::assert_panics() {
+				panic!("{message}");
+			}
+		}
+	};
+}
+
+/// Prints to the standard output like [`trace!`], but at most once per period
+/// for this call site.
+/// 
+/// The period is a [`Duration`], and each invocation of `trace_every!` tracks
+/// its own last print, shared by all threads. Suppressed calls return before
+/// the call stack is captured, so they're cheap and don't affect how the next
+/// printed line is indented. A call made while tracing is disabled or filtered
+/// out doesn't start a period.
+/// 
+/// [`trace!`]: crate::trace
+/// [`Duration`]: std::time::Duration
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace_every;
+/// use std::time::Duration;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// let lines = trace::capture_session(|| {
+///     for window in 0..2 {
+///         for i in 0..100 {
+///             trace::set_enabled(i != 0);
+///             trace_every!(Duration::from_secs(1), "window:{window}, i:{i}");
+///         }
+///         std::thread::sleep(Duration::from_millis(1100));
+///     }
+/// });
+/// 
+/// # if cfg!(feature = "trace") {
+/// assert_eq!(lines.len(), 2);
+/// assert!(lines[0].ends_with("window:0, i:1"));
+/// assert!(lines[1].ends_with("window:1, i:1"));
+/// # }
+/// ```
+#[macro_export]
+macro_rules! trace_every {
+	($period:expr $(,)?) => {
+		$crate::trace_every!($period, "")
+	};
+	($period:expr, $($arg:tt)+) => {
+		$crate::_cfg_trace!({
+			static LAST: ::std::sync::Mutex<::std::option::Option<::std::time::Instant>>
+				= ::std::sync::Mutex::new(::std::option::Option::None);
+			if $crate::_is_traced(module_path!()) && $crate::_is_due(&LAST, $period) {
+				$crate::_trace(format!($($arg)+), module_path!());
+			}
+		} else {})
+	};
+}
+
+/// Prints to the standard error, indented by the size of the local call stack.
+/// 
+/// Equivalent to the [`trace!`] macro, except that the line is printed to
+/// [`std::io::stderr`] rather than the standard output. The indentation state
+/// is shared with [`trace!`], so the two can be freely interleaved.
+/// 
+/// [`trace!`]: crate::trace
+/// 
+/// # Panics
+/// 
+/// Panics if writing to [`std::io::stderr`] fails.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::{trace, trace_err};
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// fn parse(text: &str) -> Option<u8> {
+///     trace!("parsing {text:?}");
+///     let result = text.parse().ok();
+///     if result.is_none() {
+///         trace_err!("invalid number {text:?}");
+///     }
+///     result
+/// }
+/// 
+/// trace!("Parsed: {:?}", parse("12"));
+/// trace!("Parsed: {:?}", parse("1x"));
+/// ```
+/// Output:
+/// ```text
+/// @--->---|   parsing "12"
+///     |   Parsed: Some(12)
+///     >---|   parsing "1x"
+///         |   invalid number "1x"
+///     |   Parsed: None
+/// ```
+#[macro_export]
+macro_rules! trace_err {
+	() => {
+		$crate::trace_err!("")
+	};
+	($($arg:tt)*) => {
+		$crate::_cfg_trace!({
+			if $crate::_is_traced(module_path!()) {
+				$crate::_trace_err(format!($($arg)*), module_path!());
+			}
+		} else {})
+	};
+}
+
+/// Traces an error's [`Debug`] representation where it's propagated, and
+/// returns the error unchanged.
+/// 
+/// Meant for [`Result::map_err`], like `parse().map_err(|e| trace_err_ctx!(e))?`,
+/// so that each point an error passes through shows up in the tree. The line
+/// is indented at the depth of the function handling the error rather than
+/// the closure it's made in. A format string can follow the error to describe
+/// it, which is printed before the error like `context: error`.
+/// 
+/// Equivalent to the [`trace!`] macro otherwise. The error is always
+/// returned, even if the `indent` feature is disabled.
+/// 
+/// [`Debug`]: std::fmt::Debug
+/// [`trace!`]: crate::trace
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::{trace, trace_err_ctx};
+/// use std::num::ParseIntError;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// fn parse(text: &str) -> Result<u8, ParseIntError> {
+///     trace!("parsing {text:?}");
+///     text.parse()
+/// }
+/// 
+/// fn load(text: &str) -> Result<u8, ParseIntError> {
+///     let number = parse(text).map_err(|e| trace_err_ctx!(e, "loading {text:?}"))?;
+///     Ok(number)
+/// }
+/// 
+/// let lines = trace::capture_session(|| {
+///     let result = load("1x");
+///     assert!(result.is_err());
+/// });
+/// 
+/// # if cfg!(feature = "trace") {
+/// assert_eq!(lines, [
+///     "@--->--->---|   parsing \"1x\"",
+///     "        |   loading \"1x\": ParseIntError { kind: InvalidDigit }",
+/// ]);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! trace_err_ctx {
+	($err:expr $(,)?) => {
+		match $err {
+			err => {
+				$crate::_cfg_trace!({
+					if $crate::_is_traced(module_path!()) {
+						$crate::_trace_err_ctx(format!("{:?}", &err), module_path!());
+					}
+				} else {});
+				err
+			}
+		}
+	};
+	($err:expr, $($arg:tt)+) => {
+		match $err {
+			err => {
+				$crate::_cfg_trace!({
+					if $crate::_is_traced(module_path!()) {
+						$crate::_trace_err_ctx(
+							format!("{}: {:?}", format_args!($($arg)+), &err),
+							module_path!()
+						);
+					}
+				} else {});
+				err
+			}
+		}
+	};
+}
+
+/// Prints a byte slice as a hex dump to the standard output, indented by the
+/// size of the local call stack.
+/// 
+/// The first line gives the number of bytes, followed by rows of 16 bytes each
+/// with their offset, hex values, and ASCII characters (`.` if unprintable),
+/// lined up under the trace like the lines of a multiline message. At most
+/// the given number of bytes are dumped, if any, followed by a line counting
+/// the rest. Accepts anything that implements `AsRef<[u8]>`.
+/// 
+/// Equivalent to the [`trace!`] macro otherwise.
+/// 
+/// [`trace!`]: crate::trace
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace_hex;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// let lines = trace::capture_session(|| {
+///     let bytes: Vec<u8> = (0..32).map(|i| b'A' + i).collect();
+///     trace_hex!(bytes);
+///     trace_hex!(&bytes, 4);
+///     trace_hex!(b"");
+/// });
+/// 
+/// # if cfg!(feature = "trace") {
+/// assert_eq!(lines.join("\n").lines().collect::<Vec<_>>(), [
+///     "@---|   32 bytes",
+///     "    |   00000000  41 42 43 44 45 46 47 48  49 4a 4b 4c 4d 4e 4f 50  |ABCDEFGHIJKLMNOP|",
+///     "    |   00000010  51 52 53 54 55 56 57 58  59 5a 5b 5c 5d 5e 5f 60  |QRSTUVWXYZ[\\]^_`|",
+///     "    |   32 bytes",
+///     "    |   00000000  41 42 43 44                                       |ABCD|",
+///     "    |   … 28 more bytes",
+///     "    |   0 bytes",
+/// ]);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! trace_hex {
+	($bytes:expr $(,)?) => {
+		$crate::trace_hex!($bytes, usize::MAX)
+	};
+	($bytes:expr, $limit:expr $(,)?) => {
+		$crate::_cfg_trace!({
+			if $crate::_is_traced(module_path!()) {
+				$crate::_trace(
+					$crate::_hex_dump(::core::convert::AsRef::<[u8]>::as_ref(&$bytes), $limit),
+					module_path!()
+				);
+			}
+		} else {})
+	};
+}
+
+/// Prints key/value pairs as an aligned table to the standard output, indented
+/// by the size of the local call stack.
+/// 
+/// Each pair is printed on its own line, lined up under the trace like the
+/// lines of a multiline message, with the keys' [`Display`] right-aligned to
+/// the longest key and the values' [`Debug`] representation after them. A value
+/// spanning multiple lines is indented to stay in its column.
+/// 
+/// Equivalent to the [`trace!`] macro otherwise.
+/// 
+/// [`Display`]: std::fmt::Display
+/// [`Debug`]: std::fmt::Debug
+/// [`trace!`]: crate::trace
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace_table;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// let lines = trace::capture_session(|| {
+///     let point = (1, 2);
+///     trace_table!("id" => 7, "name" => "origin", "coordinates" => point);
+/// 
+///     #[allow(dead_code)]
+///     #[derive(Debug)]
+///     struct Size { width: u8 }
+///     trace_table!("size" => format_args!("{:#?}", Size { width: 3 }), "ok" => true);
+/// });
+/// 
+/// # if cfg!(feature = "trace") {
+/// assert_eq!(lines.join("\n").lines().collect::<Vec<_>>(), [
+///     "@---|            id: 7",
+///     "    |          name: \"origin\"",
+///     "    |   coordinates: (1, 2)",
+///     "    |   size: Size {",
+///     "    |             width: 3,",
+///     "    |         }",
+///     "    |     ok: true",
+/// ]);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! trace_table {
+	($($key:expr => $val:expr),* $(,)?) => {
+		$crate::_cfg_trace!({
+			if $crate::_is_traced(module_path!()) {
+				$crate::_trace(
+					$crate::_table(&[$(
+						(::std::string::ToString::to_string(&$key), format!("{:?}", $val))
+					),*]),
+					module_path!()
+				);
+			}
+		} else {})
+	};
+}
+
+/// Logs through the [`log`] crate, indented by the size of the local call stack.
+/// 
+/// Equivalent to the [`trace!`] macro, except that the indented line is sent
+/// to the installed logger instead of the standard output. The level defaults
+/// to [`Level::Trace`], or can be given before the format string. No backtrace
+/// is captured if the logger would ignore the line at that level.
+/// 
+/// The indentation state is shared with [`trace!`], so the two can be freely
+/// interleaved on the same thread.
+/// 
+/// [`log`]: https://docs.rs/log
+/// [`trace!`]: crate::trace
+/// [`Level::Trace`]: log::Level::Trace
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace_log;
+/// use log::{Level, Log, Metadata, Record};
+/// use std::sync::Mutex;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// static LINES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+/// 
+/// struct TestLogger;
+/// 
+/// impl Log for TestLogger {
+///     fn enabled(&self, metadata: &Metadata) -> bool {
+///         metadata.level() <= Level::Debug
+///     }
+///     fn log(&self, record: &Record) {
+///         let line = format!("{} {}", record.level(), record.args());
+///         LINES.lock().unwrap().push(line);
+///     }
+///     fn flush(&self) {}
+/// }
+/// 
+/// log::set_logger(&TestLogger).unwrap();
+/// log::set_max_level(log::LevelFilter::Trace);
+/// 
+/// fn count_down(n: u8) {
+///     trace_log!(Level::Debug, "n:{n}");
+///     trace_log!("ignored");
+///     if n > 0 {
+///         count_down(n - 1);
+///     }
+/// }
+/// 
+/// count_down(1);
+/// 
+/// # if cfg!(feature = "trace") {
+/// assert_eq!(*LINES.lock().unwrap(), [
+///     "DEBUG @--->---|   n:1",
+///     "DEBUG         >---|   n:0",
+/// ]);
+/// # }
+/// ```
+#[cfg(feature = "log")]
+#[macro_export]
+macro_rules! trace_log {
+	() => {
+		$crate::trace_log!("")
+	};
+	($fmt:literal $($arg:tt)*) => {
+		$crate::trace_log!($crate::_log::Level::Trace, $fmt $($arg)*)
+	};
+	($lvl:expr, $($arg:tt)+) => {
+		$crate::_cfg_trace!({
+			let level = $lvl;
+			if $crate::_log::log_enabled!(target: module_path!(), level)
+				&& $crate::_is_traced(module_path!())
+			{
+				$crate::_trace_log(level, format!($($arg)+), module_path!());
+			}
+		} else {})
+	};
+}
+
+/// Prints an entry line, like `→ name`, indented like [`trace!`].
+/// 
+/// Paired with [`trace_exit!`] at the end of the same function, the two lines
+/// are printed at the same depth, with any traces in between nested below.
+/// For an exit line that's printed automatically, see [`trace_scope!`].
+/// 
+/// [`trace!`]: crate::trace
+/// [`trace_exit!`]: crate::trace_exit
+/// [`trace_scope!`]: crate::trace_scope
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::{trace, trace_enter, trace_exit};
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// fn parse(text: &str) -> usize {
+///     trace_enter!("parse {text:?}");
+///     let len = count(text);
+///     trace_exit!("parse");
+///     len
+/// }
+/// 
+/// fn count(text: &str) -> usize {
+///     trace!("counting");
+///     text.len()
+/// }
+/// 
+/// let lines = trace::capture_session(|| {
+///     parse("abc");
+/// });
+/// 
+/// # if cfg!(feature = "trace") {
+/// assert_eq!(lines, [
+///     "@--->---|   → parse \"abc\"",
+///     "        >---|   counting",
+///     "        |   ← parse",
+/// ]);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! trace_enter {
+	($($arg:tt)+) => {
+		$crate::trace!("→ {}", format_args!($($arg)+))
+	};
+}
+
+/// Prints an exit line, like `← name`, indented like [`trace!`]. See
+/// [`trace_enter!`].
+/// 
+/// [`trace!`]: crate::trace
+/// [`trace_enter!`]: crate::trace_enter
+#[macro_export]
+macro_rules! trace_exit {
+	($($arg:tt)+) => {
+		$crate::trace!("← {}", format_args!($($arg)+))
+	};
+}
+
+/// Makes the calling function the baseline of the traces within it on this
+/// thread, in place of the first function of the tracing crate.
+/// 
+/// Traces made within the anchored call (including in the function itself) are
+/// indented relative to it, so it's marked as the baseline (`@`). Traces made
+/// outside of it are indented as usual. The anchor lasts until another
+/// `trace_anchor!` or [`reset`], and nothing is printed.
+/// 
+/// [`reset`]: crate::reset
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::{trace, trace_anchor};
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// fn run() {
+///     helper();
+/// }
+/// 
+/// fn helper() {
+///     trace_anchor!();
+///     trace!("helper");
+///     step();
+/// }
+/// 
+/// fn step() {
+///     trace!("step");
+/// }
+/// 
+/// let lines = trace::capture_session(|| {
+///     run();
+/// });
+/// 
+/// # if cfg!(feature = "trace") {
+/// assert_eq!(lines, ["@   helper", ">---|   step"]);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! trace_anchor {
+	() => {
+		$crate::_cfg_trace!({
+			$crate::_trace_anchor();
+		} else {})
+	};
+}
+
+/// Prints a header line like [`trace!`], then indents every following trace on
+/// this thread one extra level until a matching [`trace_group_end!`].
+/// 
+/// Unlike the indentation derived from the call stack, groups are explicit, so
+/// they can mark out phases within a single function. Groups nest, and each
+/// open group adds one more level to the indentation, drawn just before the
+/// trace's own symbol. The group is started even if the header is disabled or
+/// filtered, and [`reset`] ends all open groups.
+/// 
+/// [`trace!`]: crate::trace
+/// [`trace_group_end!`]: crate::trace_group_end
+/// [`reset`]: crate::reset
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::{trace, trace_group, trace_group_end};
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// fn step(n: u8) {
+///     trace!("step {n}");
+/// }
+/// 
+/// let lines = trace::capture_session(|| {
+///     step(0);
+///     trace_group!("setup");
+///     step(1);
+///     step(2);
+///     trace_group_end!();
+///     step(3);
+/// });
+/// 
+/// # if cfg!(feature = "trace") {
+/// assert_eq!(lines, [
+///     "@--->---|   step 0",
+///     "    |   setup",
+///     "    >---|   |   step 1",
+///     "        |   |   step 2",
+///     "        |   step 3",
+/// ]);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! trace_group {
+	($($arg:tt)+) => {
+		$crate::_cfg_trace!({
+			$crate::trace!($($arg)+);
+			$crate::_start_group();
+		} else {})
+	};
+}
+
+/// Ends the innermost group started by [`trace_group!`]. Does nothing if no
+/// group is open.
+/// 
+/// [`trace_group!`]: crate::trace_group
+#[macro_export]
+macro_rules! trace_group_end {
+	() => {
+		$crate::_cfg_trace!({
+			$crate::_end_group();
+		} else {})
+	};
+}
+
+/// Returns the line [`trace!`] would print, without printing it.
+/// 
+/// The line is indented (and prefixed) as usual, without a trailing newline,
+/// and the indentation state is updated as if it had been printed. If the trace
+/// is disabled or filtered, an empty string is returned.
+/// 
+/// [`trace!`]: crate::trace
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::{trace_string, trace_to};
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// let line = trace_string!("x = {}", 1);
+/// trace::reset();
+/// let mut out = Vec::new();
+/// trace_to!(&mut out, "x = {}", 1).unwrap();
+/// 
+/// # if cfg!(feature = "trace") {
+/// assert_eq!(line + "\n", String::from_utf8(out).unwrap());
+/// # }
+/// ```
+#[macro_export]
+macro_rules! trace_string {
+	() => {
+		$crate::trace_string!("")
+	};
+	($($arg:tt)*) => {
+		$crate::_cfg_trace!({
+			if $crate::_is_traced(module_path!()) {
+				$crate::_trace_string(format!($($arg)*), module_path!())
+			} else {
+				::std::string::String::new()
+			}
+		} else {
+			::std::string::String::new()
+		})
+	};
+}
+
+/// Prints only the indentation that [`trace!`] would print here, as a probe of
+/// the current depth.
+/// 
+/// The line ends at the trace's own symbol (`|` by default), without a
+/// message or trailing spaces. The indentation state is updated like any
+/// other trace, so a following trace is indented relative to this one.
+/// 
+/// [`trace!`]: crate::trace
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::{peek_depth, trace};
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// fn inner() {
+///     peek_depth!();
+///     trace!("inner");
+/// }
+/// 
+/// let lines = trace::capture_session(|| {
+///     peek_depth!();
+///     inner();
+/// });
+/// 
+/// # if cfg!(feature = "trace") {
+/// assert_eq!(lines, [
+///     "@---|",
+///     "    >---|",
+///     "        |   inner",
+/// ]);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! peek_depth {
+	() => {
+		$crate::_cfg_trace!({
+			if $crate::_is_traced(module_path!()) {
+				$crate::_trace_peek(module_path!());
+			}
+		} else {})
+	};
+}
+
+/// Prints each line of a block, like captured output, indented to the depth
+/// that [`trace!`] would print at here.
+/// 
+/// The lines are indented like the continuation lines of a multiline trace, so
+/// the block nests under the traces around it. The block accepts anything
+/// that implements `AsRef<str>`, and isn't formatted. Like [`peek_depth!`], the
+/// indentation state is updated as if a trace was made.
+/// 
+/// [`trace!`]: crate::trace
+/// [`peek_depth!`]: crate::peek_depth
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::{indent_raw, trace};
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// fn run() {
+///     trace!("running");
+///     let output = String::from("line 1\nline 2\nline 3\n");
+///     indent_raw!(output);
+/// }
+/// 
+/// let lines = trace::capture_session(|| {
+///     run();
+/// });
+/// 
+/// # if cfg!(feature = "trace") {
+/// assert_eq!(lines, [
+///     "@--->---|   running",
+///     "        |   line 1\n        |   line 2\n        |   line 3",
+/// ]);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! indent_raw {
+	($block:expr $(,)?) => {
+		$crate::_cfg_trace!({
+			if $crate::_is_traced(module_path!()) {
+				$crate::_trace_raw(::std::convert::AsRef::<str>::as_ref(&$block), module_path!());
+			}
+		} else {})
+	};
+}
+
+/// Prints an entry line, and an exit line once the returned guard is dropped.
+/// 
+/// Equivalent to calling [`trace!`] with `"{label} {"` at the start of a block
+/// and `"}"` at its end, where the label accepts the same arguments as
+/// [`format!`]. Both lines are indented to the depth where the guard was
+/// created, so traces within the block nest visually between them.
+/// 
+/// [`trace!`]: crate::trace
+/// [`format!`]: std::format
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::{trace, trace_scope};
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// fn s(n: u8, k: u8) -> u8 {
+///     let _scope = trace_scope!("s({n}, {k})");
+///     if n == k {
+///         return 1
+///     }
+///     if k == 0 || n < k {
+///         return 0
+///     }
+///     s(n-1, k-1) + s(n-1, k)*k
+/// }
+/// 
+/// trace!("Result: {}", s(2, 1));
+/// ```
+/// Output:
+/// ```text
+/// @--->---|   s(2, 1) {
+///         >---|   s(1, 0) {
+///             |   }
+///             |   s(1, 1) {
+///             |   }
+///         |   }
+///     |   Result: 1
+/// ```
+#[macro_export]
+macro_rules! trace_scope {
+	($($arg:tt)+) => {
+		$crate::_cfg_trace!({
+			let label = if $crate::_is_traced(module_path!()) {
+				::std::option::Option::Some(format!($($arg)+))
+			} else {
+				::std::option::Option::None
+			};
+			$crate::_trace_scope(label, module_path!())
+		} else {})
+	};
+}
+
+/// Writes to the given writer, indented by the size of the local call stack.
+/// 
+/// Equivalent to the [`trace!`] macro, except that the line is written into
+/// `writer` (any `&mut impl io::Write`) rather than the standard output. Like
+/// [`writeln!`], the result of the write is returned as an [`io::Result`].
+/// 
+/// The indentation state is shared with [`trace!`], so the two can be freely
+/// interleaved on the same thread. A trace made from within `writer` itself is
+/// written without indentation, and a `writer` that panics leaves later traces
+/// unaffected.
+/// 
+/// [`trace!`]: crate::trace
+/// [`writeln!`]: std::writeln
+/// [`io::Result`]: std::io::Result
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace_to;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// fn count_down(out: &mut Vec<u8>, n: u8) {
+///     trace_to!(out, "n:{n}").unwrap();
+///     if n > 0 {
+///         count_down(out, n - 1);
+///     }
+/// }
+/// 
+/// let mut out = Vec::new();
+/// trace_to!(&mut out, "start").unwrap();
+/// count_down(&mut out, 2);
+/// 
+/// # if cfg!(feature = "trace") {
+/// assert_eq!(String::from_utf8(out).unwrap(), "\
+/// @---|   start
+///     >---|   n:2
+///         >---|   n:1
+///             >---|   n:0
+/// ");
+/// # }
+/// ```
+/// 
+/// A spawned thread starts from a clean baseline, regardless of what the thread
+/// that spawned it traced:
+/// 
+/// ```
+/// use trace::trace_to;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// fn nested(out: &mut Vec<u8>) {
+///     trace_to!(out, "nested").unwrap();
+/// }
+/// 
+/// let mut out = Vec::new();
+/// nested(&mut out);
+/// let worker = std::thread::spawn(|| {
+///     let mut out = Vec::new();
+///     trace_to!(&mut out, "worker").unwrap();
+///     out
+/// });
+/// 
+/// # if cfg!(feature = "trace") {
+/// let worker = String::from_utf8(worker.join().unwrap()).unwrap();
+/// assert!(worker.starts_with('@') && !worker.contains('>'));
+/// # }
+/// ```
+/// 
+/// Each line of a multiline message is indented to match, with Windows line
+/// endings (`\r\n`) treated as `\n`:
+/// 
+/// ```
+/// use trace::trace_to;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// let mut out = Vec::new();
+/// trace_to!(&mut out, "first\r\nsecond\nthird").unwrap();
+/// 
+/// # if cfg!(feature = "trace") {
+/// assert_eq!(String::from_utf8(out).unwrap(), "\
+/// @---|   first
+///     |   second
+///     |   third
+/// ");
+/// # }
+/// ```
+/// 
+/// A panicking writer:
+/// 
+/// ```
+/// use trace::trace_to;
+/// use std::io;
+/// 
+/// struct Panicky;
+/// 
+/// impl io::Write for Panicky {
+///     fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+///         panic!("writer failed");
+///     }
+///     fn flush(&mut self) -> io::Result<()> {
+///         Ok(())
+///     }
+/// }
+/// 
+/// let result = std::panic::catch_unwind(|| trace_to!(&mut Panicky, "lost"));
+/// # if cfg!(feature = "trace") {
+/// assert!(result.is_err());
+/// # }
+/// 
+/// let mut out = Vec::<u8>::new();
+/// trace_to!(&mut out, "still tracing").unwrap();
+/// # if cfg!(feature = "trace") {
+/// assert!(String::from_utf8(out).unwrap().ends_with("still tracing\n"));
+/// # }
+/// ```
+#[macro_export]
+macro_rules! trace_to {
+	($dst:expr) => {
+		$crate::trace_to!($dst, "")
+	};
+	($dst:expr, $($arg:tt)*) => {
+		$crate::_cfg_trace!({
+			if $crate::_is_traced(module_path!()) {
+				$crate::_trace_to($dst, format!($($arg)*), module_path!())
+			} else {
+				::std::io::Result::Ok(())
+			}
+		} else {
+			::std::io::Result::Ok(())
+		})
+	};
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(true);
+static IS_ENABLED_SET: AtomicBool = AtomicBool::new(false);
+
+/// Enables or disables all tracing at runtime. Enabled by default.
+/// 
+/// While disabled, calls to [`trace!`] (and its variants) return immediately
+/// without formatting their message, capturing a backtrace, or printing
+/// anything. The same goes for traces ignored by [`set_module_filter`] and
+/// [`set_module_blocklist`].
+/// 
+/// Tracing can also be disabled by the `TRACE_DISABLE` environment variable,
+/// as `1`, `true`, `yes`, or `on` (ignoring case), which is read once at the
+/// first trace. Unlike the other environment variables, it's ignored if this
+/// function was called before then, and calling this function afterwards can
+/// still re-enable tracing.
+/// 
+/// [`trace!`]: crate::trace
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace_to;
+/// use std::cell::Cell;
+/// use std::fmt;
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// struct Counted<'a>(&'a Cell<u32>);
+/// 
+/// impl fmt::Display for Counted<'_> {
+///     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+///         self.0.set(self.0.get() + 1);
+///         write!(f, "counted")
+///     }
+/// }
+/// 
+/// // Count each call stack captured:
+/// static CAPTURES: AtomicUsize = AtomicUsize::new(0);
+/// trace::set_frame_provider(Box::new(|| {
+///     CAPTURES.fetch_add(1, Ordering::Relaxed);
+///     vec!["app::main".to_owned()]
+/// }));
+/// 
+/// let count = Cell::new(0);
+/// let mut out: Vec<u8> = Vec::new();
+/// trace::set_enabled(false);
+/// trace_to!(&mut out, "hidden {}", Counted(&count)).unwrap();
+/// assert!(out.is_empty());
+/// assert_eq!(count.get(), 0);
+/// assert_eq!(CAPTURES.load(Ordering::Relaxed), 0);
+/// 
+/// trace::set_enabled(true);
+/// trace_to!(&mut out, "shown").unwrap();
+/// # if cfg!(feature = "indent") {
+/// assert!(!out.is_empty());
+/// assert_eq!(CAPTURES.load(Ordering::Relaxed), 1);
+/// # }
+/// ```
+/// 
+/// Using the environment variable:
+/// 
+/// ```
+/// use trace::trace;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// std::env::set_var("TRACE_DISABLE", "1");
+/// 
+/// let lines = trace::capture_session(|| {
+///     trace!("hidden");
+/// });
+/// assert!(lines.is_empty());
+/// # if cfg!(feature = "indent") {
+/// assert!(!trace::is_enabled());
+/// # }
+/// 
+/// trace::set_enabled(true);
+/// let lines = trace::capture_session(|| {
+///     trace!("shown");
+/// });
+/// # if cfg!(feature = "indent") {
+/// assert_eq!(lines.len(), 1);
+/// # }
+/// ```
+pub fn set_enabled(enabled: bool) {
+	ENABLED.store(enabled, Ordering::Relaxed);
+	IS_ENABLED_SET.store(true, Ordering::Relaxed);
+}
+
+/// Returns whether tracing is currently enabled. See [`set_enabled`].
+pub fn is_enabled() -> bool {
+	ENABLED.load(Ordering::Relaxed)
+}
+
+/// A single symbol of indentation, drawn as a head character followed by a
+/// repeated fill character (e.g. `>---` is `Symbol::new('>', '-')`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Symbol {
+	pub head: char,
+	pub fill: char,
+}
+
+impl Symbol {
+	pub const fn new(head: char, fill: char) -> Self {
+		Self { head, fill }
+	}
+	
+	#[cfg(feature = "indent")]
+	fn render(self, width: usize) -> String {
+		//! Returns this symbol drawn `width` characters wide.
+		
+		let mut text = String::with_capacity(width);
+		text.push(self.head);
+		text.extend(std::iter::repeat_n(self.fill, width - 1));
+		text
+	}
+}
+
+/// The symbols and layout used to indent each trace. See [`set_config`].
+/// 
+/// Widths are measured in characters, so each symbol's characters should
+/// occupy a single display column.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::{trace_to, Symbol, TraceConfig};
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// fn count_down(out: &mut Vec<u8>, n: u8) {
+///     trace_to!(out, "n:{n}").unwrap();
+///     if n > 0 {
+///         count_down(out, n - 1);
+///     }
+/// }
+/// 
+/// trace::set_config(TraceConfig {
+///     baseline:   Symbol::new('┬', '─'),
+///     divergence: Symbol::new('└', '─'),
+///     current:    Symbol::new('┤', ' '),
+///     ..TraceConfig::default()
+/// });
+/// let mut out = Vec::new();
+/// count_down(&mut out, 2);
+/// 
+/// # if cfg!(feature = "trace") {
+/// assert_eq!(String::from_utf8(out).unwrap(), "\
+/// ┬───└───┤   n:2
+///         └───┤   n:1
+///             └───┤   n:0
+/// ");
+/// # }
+/// ```
+/// 
+/// The continuation lines of a multiline trace can connect like a tree node.
+/// A lone continuation line gets the last connector, and a trailing newline
+/// leaves an empty last line:
+/// 
+/// ```
+/// use trace::{trace_to, Symbol, TraceConfig};
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// trace::set_config(TraceConfig {
+///     first_continuation: Some(Symbol::new('├', '─')),
+///     continuation:       Some(Symbol::new('│', ' ')),
+///     last_continuation:  Some(Symbol::new('└', '─')),
+///     ..TraceConfig::default()
+/// });
+/// let mut out = Vec::new();
+/// trace_to!(&mut out, "node\nfirst\nmiddle\nlast").unwrap();
+/// trace_to!(&mut out, "node\nlone").unwrap();
+/// trace_to!(&mut out, "node\nline\n").unwrap();
+/// 
+/// # if cfg!(feature = "trace") {
+/// assert_eq!(String::from_utf8(out).unwrap(), "\
+/// @---|   node
+///     ├───first
+///     │   middle
+///     └───last
+///     |   node
+///     └───lone
+///     |   node
+///     ├───line
+///     └───
+/// ");
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TraceConfig {
+	/// The number of characters that each level of indentation occupies.
+	pub indent_width: usize,
+	
+	/// Marks the baseline depth, like a main function or thread (`@---`).
+	/// The fill of [`current`](Self::current) is used if nothing follows it.
+	pub baseline: Symbol,
+	
+	/// Marks depths where the call stack differs from the last trace (`>---`).
+	pub divergence: Symbol,
+	
+	/// Marks the current depth relative to the baseline (`|   `).
+	pub current: Symbol,
+	
+	/// Marks depths where the call stack matches the last trace (`    `).
+	pub matched: Symbol,
+	
+	/// Marks the first continuation line of a multiline trace, unless it's also
+	/// the last. Uses [`current`](Self::current) if `None` (the default).
+	pub first_continuation: Option<Symbol>,
+	
+	/// Marks the continuation lines of a multiline trace between its first and
+	/// last. Uses [`current`](Self::current) if `None` (the default).
+	pub continuation: Option<Symbol>,
+	
+	/// Marks the last continuation line of a multiline trace, including one
+	/// that's also the first. Uses [`current`](Self::current) if `None` (the
+	/// default).
+	pub last_continuation: Option<Symbol>,
+	
+	/// The number of fill characters after the head of the final symbol and
+	/// the continuation symbols, which separate it from the message. Uses one
+	/// less than [`indent_width`](Self::indent_width) if `None` (the default).
+	pub gutter: Option<usize>,
+}
+
+impl TraceConfig {
+	pub const fn new() -> Self {
+		Self {
+			indent_width: 4,
+			baseline:     Symbol::new('@', '-'),
+			divergence:   Symbol::new('>', '-'),
+			current:      Symbol::new('|', ' '),
+			matched:      Symbol::new(' ', ' '),
+			first_continuation: None,
+			continuation:       None,
+			last_continuation:  None,
+			gutter:             None,
+		}
+	}
+}
+
+impl Default for TraceConfig {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+static CONFIG: RwLock<TraceConfig> = RwLock::new(TraceConfig::new());
+
+fn read_lock<T>(lock: &RwLock<T>) -> RwLockReadGuard<'_, T> {
+	//! Locks a setting for reading, ignoring poisoning since every write is a
+	//! plain assignment that can't leave the value half-updated.
+	
+	lock.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn write_lock<T>(lock: &RwLock<T>) -> RwLockWriteGuard<'_, T> {
+	//! Locks a setting for writing, ignoring poisoning. See [`read_lock`].
+	
+	lock.write().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+	//! Locks a setting, ignoring poisoning. See [`read_lock`].
+	
+	mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Sets the symbols and layout used to indent each trace.
+/// 
+/// An `indent_width` of 0 is clamped to 1.
+pub fn set_config(mut config: TraceConfig) {
+	config.indent_width = config.indent_width.max(1);
+	*write_lock(&CONFIG) = config;
+}
+
+/// Returns the current trace configuration. See [`set_config`].
+pub fn config() -> TraceConfig {
+	*read_lock(&CONFIG)
+}
+
+/// Sets the trace configuration while running a closure, then restores the
+/// previous configuration, even if the closure panics. See [`set_config`].
+/// 
+/// The configuration is still process-wide, so other threads tracing in the
+/// meantime use it too, and nested calls should return in the order they were
+/// made.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::TraceConfig;
+/// 
+/// let outer = TraceConfig { indent_width: 2, ..TraceConfig::default() };
+/// let inner = TraceConfig { indent_width: 8, ..TraceConfig::default() };
+/// trace::with_config(outer, || {
+///     trace::with_config(inner, || {
+///         assert_eq!(trace::indent_width(), 8);
+///     });
+///     assert_eq!(trace::indent_width(), 2);
+/// });
+/// assert_eq!(trace::indent_width(), 4);
+/// 
+/// let result = std::panic::catch_unwind(|| {
+///     trace::with_config(inner, || panic!("restored anyway"));
+/// });
+/// assert!(result.is_err());
+/// assert_eq!(trace::indent_width(), 4);
+/// ```
+pub fn with_config<F>(config: TraceConfig, f: F)
+where
+	F: FnOnce()
+{
+	struct ConfigGuard(TraceConfig);
+	
+	impl Drop for ConfigGuard {
+		fn drop(&mut self) {
+			set_config(self.0);
+		}
+	}
+	
+	let _guard = ConfigGuard(self::config());
+	set_config(config);
+	f();
+}
+
+static IS_INDENT_WIDTH_SET: AtomicBool = AtomicBool::new(false);
+
+/// Sets the number of characters that each level of indentation occupies.
+/// 
+/// The default width is 4. A width of 0 is clamped to 1, which still leaves
+/// each indentation symbol distinguishable by its leading character.
+/// 
+/// This can also be set by the `TRACE_INDENT` environment variable, which is
+/// read once at the first trace and is ignored if this function was called
+/// first, like `TRACE_DISABLE` for [`set_enabled`]. Values that aren't a
+/// positive integer are ignored.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace_to;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// fn count_down(out: &mut Vec<u8>, n: u8) {
+///     trace_to!(out, "n:{n}").unwrap();
+///     if n > 0 {
+///         count_down(out, n - 1);
+///     }
+/// }
+/// 
+/// // The environment variable doesn't override an explicit call:
+/// std::env::set_var("TRACE_INDENT", "8");
+/// trace::set_indent_width(2);
+/// let mut out = Vec::new();
+/// count_down(&mut out, 2);
+/// 
+/// # if cfg!(feature = "trace") {
+/// assert_eq!(String::from_utf8(out).unwrap(), "\
+/// @->-| n:2
+///     >-| n:1
+///       >-| n:0
+/// ");
+/// # }
+/// ```
+pub fn set_indent_width(width: usize) {
+	IS_INDENT_WIDTH_SET.store(true, Ordering::Relaxed);
+	set_config(TraceConfig {
+		indent_width: width,
+		..config()
+	});
+}
+
+/// Returns the current indentation width. See [`set_indent_width`].
+pub fn indent_width() -> usize {
+	config().indent_width
+}
+
+/// Sets the number of spaces between the final `|` or `@` marker and the
+/// message, independently of the indentation width. Defaults to one less than
+/// the width, or 3.
+/// 
+/// This is [`TraceConfig::gutter`], which also applies to the continuation
+/// lines of a multiline trace so that they stay aligned with the message.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace_to;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// fn count_down(out: &mut Vec<u8>, n: u8) {
+///     trace_to!(out, "n:{n}").unwrap();
+///     if n > 0 {
+///         count_down(out, n - 1);
+///     }
+/// }
+/// 
+/// trace::set_gutter(1);
+/// let mut out = Vec::new();
+/// count_down(&mut out, 2);
+/// 
+/// # if cfg!(feature = "trace") {
+/// assert_eq!(String::from_utf8(out).unwrap(), "\
+/// @--->---| n:2
+///         >---| n:1
+///             >---| n:0
+/// ");
+/// # }
+/// assert_eq!(trace::gutter(), 1);
+/// ```
+pub fn set_gutter(gutter: usize) {
+	set_config(TraceConfig {
+		gutter: Some(gutter),
+		..config()
+	});
+}
+
+/// Returns the number of spaces between the final marker and the message. See
+/// [`set_gutter`].
+pub fn gutter() -> usize {
+	let config = config();
+	config.gutter.unwrap_or(config.indent_width - 1)
+}
+
+/// Sets the character drawn at the start of each depth where the call stack
+/// matches the last trace, as a guide for counting levels (e.g. `·` or `│`).
+/// 
+/// This is the head of [`TraceConfig::matched`], which is blank by default.
+/// The divergence and baseline markers are unaffected.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace_to;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// fn s(out: &mut Vec<u8>, n: u8, k: u8) -> u8 {
+///     trace_to!(out, "n:{n}, k:{k}").unwrap();
+///     if n == k {
+///         return 1
+///     }
+///     if k == 0 || n < k {
+///         return 0
+///     }
+///     s(out, n-1, k-1) + s(out, n-1, k)*k
+/// }
+/// 
+/// trace::set_guide_fill('·');
+/// let mut out = Vec::new();
+/// s(&mut out, 3, 2);
+/// 
+/// # if cfg!(feature = "trace") {
+/// assert_eq!(String::from_utf8(out).unwrap(), "\
+/// @--->---|   n:3, k:2
+/// ·   ·   >---|   n:2, k:1
+/// ·   ·   ·   >---|   n:1, k:0
+/// ·   ·   ·   ·   |   n:1, k:1
+/// ·   ·   ·   |   n:2, k:2
+/// ");
+/// # }
+/// ```
+pub fn set_guide_fill(guide: char) {
+	set_config(TraceConfig {
+		matched: Symbol::new(guide, ' '),
+		..config()
+	});
+}
+
+/// Controls whether the indentation markers are colored. See [`set_color_mode`].
+#[cfg(feature = "color")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorMode {
+	/// Colors output to a terminal, unless the [`NO_COLOR`] environment
+	/// variable is set.
+	/// 
+	/// [`NO_COLOR`]: https://no-color.org/
+	#[default]
+	Auto,
+	
+	/// Always colors output, even when writing to a file or buffer.
+	Always,
+	
+	/// Never colors output.
+	Never,
+}
+
+#[cfg(feature = "color")]
+static COLOR_MODE: RwLock<ColorMode> = RwLock::new(ColorMode::Auto);
+
+/// Sets when the indentation markers are colored. Defaults to [`ColorMode::Auto`].
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::{trace_to, ColorMode};
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// fn count_down(out: &mut Vec<u8>, n: u8) {
+///     trace_to!(out, "n:{n}").unwrap();
+///     if n > 0 {
+///         count_down(out, n - 1);
+///     }
+/// }
+/// 
+/// trace::set_color_mode(ColorMode::Always);
+/// let mut out = Vec::new();
+/// count_down(&mut out, 1);
+/// 
+/// # if cfg!(feature = "trace") {
+/// assert_eq!(String::from_utf8(out).unwrap(), "\
+/// \x1b[36m@---\x1b[0m\x1b[33m>---\x1b[0m|   n:1
+///         \x1b[33m>---\x1b[0m|   n:0
+/// ");
+/// # }
+/// 
+/// trace::set_color_mode(ColorMode::Never);
+/// let mut out = Vec::new();
+/// count_down(&mut out, 1);
+/// assert!(!out.contains(&b'\x1b'));
+/// ```
+#[cfg(feature = "color")]
+pub fn set_color_mode(mode: ColorMode) {
+	*write_lock(&COLOR_MODE) = mode;
+}
+
+/// Returns the current color mode. See [`set_color_mode`].
+#[cfg(feature = "color")]
+pub fn color_mode() -> ColorMode {
+	*read_lock(&COLOR_MODE)
+}
+
+static TIMESTAMPS: AtomicBool = AtomicBool::new(false);
+
+/// Enables or disables prefixing each line with a timestamp. Disabled by default.
+/// 
+/// Timestamps are printed left of the indentation as a fixed-width count of
+/// seconds since the first timestamped line, like `[  1.234] `. The lines of a
+/// multiline message are padded to line up under the first.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace_to;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// trace::set_timestamps(true);
+/// let mut out = Vec::new();
+/// trace_to!(&mut out, "start").unwrap();
+/// trace_to!(&mut out, "first\nsecond").unwrap();
+/// 
+/// # if cfg!(feature = "trace") {
+/// let out = String::from_utf8(out).unwrap();
+/// let lines: Vec<&str> = out.lines().collect();
+/// let (timestamp, trace) = lines[0].split_at(10);
+/// assert!(timestamp.starts_with("[  0.0") && timestamp.ends_with("] "));
+/// assert_eq!(trace, "@---|   start");
+/// assert_eq!(lines[1].find("first"), lines[2].find("second"));
+/// # }
+/// ```
+pub fn set_timestamps(enabled: bool) {
+	TIMESTAMPS.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns whether timestamps are enabled. See [`set_timestamps`].
+pub fn timestamps() -> bool {
+	TIMESTAMPS.load(Ordering::Relaxed)
+}
+
+static SHOW_DELTAS: AtomicBool = AtomicBool::new(false);
+
+/// Enables or disables prefixing each line with the time elapsed since the
+/// previous trace on the same thread, like `+1.23ms`. Disabled by default.
+/// 
+/// The first line traced after enabling this shows `+0.00ms`.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace_to;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// trace::set_show_deltas(true);
+/// let mut out = Vec::new();
+/// trace_to!(&mut out, "start").unwrap();
+/// std::thread::sleep(std::time::Duration::from_millis(20));
+/// trace_to!(&mut out, "end").unwrap();
+/// 
+/// # if cfg!(feature = "trace") {
+/// let out = String::from_utf8(out).unwrap();
+/// let deltas: Vec<f64> = out.lines()
+///     .map(|line| line[1..].split_once("ms").unwrap().0.parse().unwrap())
+///     .collect();
+/// assert_eq!(deltas[0], 0.);
+/// assert!(deltas[1] >= 20.);
+/// # }
+/// ```
+pub fn set_show_deltas(enabled: bool) {
+	SHOW_DELTAS.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns whether deltas are shown. See [`set_show_deltas`].
+pub fn show_deltas() -> bool {
+	SHOW_DELTAS.load(Ordering::Relaxed)
+}
+
+static SHOW_SEQUENCE: AtomicBool = AtomicBool::new(false);
+
+/// The sequence number of the next line. See [`set_show_sequence`].
+#[cfg(feature = "indent")]
+static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+/// Enables or disables prefixing each line with a sequence number, like
+/// `#000123`, shared by all threads. Disabled by default.
+/// 
+/// The numbers increase with each line printed by any thread, so lines that
+/// are collected out of order (like from several files) can be put back in
+/// the order they were printed. They're placed after the thread name, if shown
+/// (see [`set_show_thread`]), and padded to six digits.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// let lines = trace::capture_session(|| {
+///     trace::set_show_sequence(true);
+///     let workers: Vec<_> = (0..4)
+///         .map(|n| std::thread::spawn(move || {
+///             for i in 0..25 {
+///                 trace!("worker {n}, job {i}");
+///             }
+///         }))
+///         .collect();
+///     for worker in workers {
+///         worker.join().unwrap();
+///     }
+/// });
+/// 
+/// # if cfg!(feature = "trace") {
+/// let mut sequence: Vec<u64> = lines.iter()
+///     .map(|line| line[1..7].parse().unwrap())
+///     .collect();
+/// sequence.sort();
+/// assert_eq!(sequence.len(), 100);
+/// assert!(sequence.windows(2).all(|pair| pair[0] < pair[1]));
+/// # }
+/// ```
+pub fn set_show_sequence(enabled: bool) {
+	SHOW_SEQUENCE.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns whether sequence numbers are shown. See [`set_show_sequence`].
+pub fn show_sequence() -> bool {
+	SHOW_SEQUENCE.load(Ordering::Relaxed)
+}
+
+static SHOW_THREAD: AtomicBool = AtomicBool::new(false);
+
+/// Enables or disables prefixing each line with the name of the thread that
+/// traced it, like `[worker-1]`. Disabled by default.
+/// 
+/// Unnamed threads are labeled by their [`ThreadId`] instead. The label is
+/// padded to a fixed width and placed left of any other prefix, so the
+/// indentation of each thread still lines up.
+/// 
+/// [`ThreadId`]: std::thread::ThreadId
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace_to;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// trace::set_show_thread(true);
+/// let workers: Vec<_> = (1..=2)
+///     .map(|n| std::thread::Builder::new()
+///         .name(format!("worker-{n}"))
+///         .spawn(move || {
+///             let mut out = Vec::new();
+///             for i in 0..3 {
+///                 trace_to!(&mut out, "job {i}").unwrap();
+///             }
+///             out
+///         })
+///         .unwrap())
+///     .collect();
+/// 
+/// # if cfg!(feature = "trace") {
+/// for (n, worker) in (1..=2).zip(workers) {
+///     let out = String::from_utf8(worker.join().unwrap()).unwrap();
+///     assert_eq!(out.lines().count(), 3);
+///     for line in out.lines() {
+///         assert!(line.starts_with(&format!("[worker-{n}]     ")));
+///     }
+/// }
+/// # }
+/// ```
+pub fn set_show_thread(enabled: bool) {
+	SHOW_THREAD.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns whether thread names are shown. See [`set_show_thread`].
+pub fn show_thread() -> bool {
+	SHOW_THREAD.load(Ordering::Relaxed)
+}
+
+static SHOW_LOCATION: AtomicBool = AtomicBool::new(false);
+
+/// Enables or disables appending the source location of each trace to its
+/// line, like ` (src/main.rs:42)`. Disabled by default.
+/// 
+/// The location is that of the macro call, so it's reliable even where the
+/// call stack has no debug info. The closing line of a [`trace_scope!`] has no
+/// location.
+/// 
+/// [`trace_scope!`]: crate::trace_scope
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace_to;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// trace::set_show_location(true);
+/// let mut out = Vec::new();
+/// trace_to!(&mut out, "here").unwrap(); let line = line!();
+/// 
+/// # if cfg!(feature = "trace") {
+/// let out = String::from_utf8(out).unwrap();
+/// assert!(out.ends_with(&format!("here ({}:{line})\n", file!())));
+/// # }
+/// ```
+pub fn set_show_location(enabled: bool) {
+	SHOW_LOCATION.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns whether source locations are shown. See [`set_show_location`].
+pub fn show_location() -> bool {
+	SHOW_LOCATION.load(Ordering::Relaxed)
+}
+
+static SHOW_FUNCTION: AtomicBool = AtomicBool::new(false);
+
+/// Enables or disables prefixing each message with the name of the function
+/// that traced it, like `[parse]`. Disabled by default.
+/// 
+/// By default, the name is shortened to its last segment, without generic
+/// arguments, and closures are shown as `[parse::{closure}]` (see
+/// [`set_symbol_style`]). Lines printed without a call stack have no name.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace_to;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// fn s(out: &mut Vec<u8>, n: u8, k: u8) -> u8 {
+///     trace_to!(out, "n:{n}, k:{k}").unwrap();
+///     if n == k {
+///         return 1
+///     }
+///     if k == 0 || n < k {
+///         return 0
+///     }
+///     s(out, n-1, k-1) + s(out, n-1, k)*k
+/// }
+/// 
+/// trace::set_show_function(true);
+/// let mut out = Vec::new();
+/// s(&mut out, 3, 2);
+/// 
+/// # if cfg!(feature = "trace") {
+/// let out = String::from_utf8(out).unwrap();
+/// assert_eq!(out.lines().count(), 5);
+/// for line in out.lines() {
+///     assert!(line.contains("|   [s] n:"));
+/// }
+/// # }
+/// ```
+pub fn set_show_function(enabled: bool) {
+	SHOW_FUNCTION.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns whether function names are shown. See [`set_show_function`].
+pub fn show_function() -> bool {
+	SHOW_FUNCTION.load(Ordering::Relaxed)
+}
+
+/// How function names are shown by [`set_show_function`]. See
+/// [`set_symbol_style`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SymbolStyle {
+	/// The last segment of the path, without generic arguments, like `parse`
+	/// or `parse::{closure}`.
+	#[default]
+	Short,
+	
+	/// The full demangled path, without its hash, including any generic
+	/// arguments and closures, like `app::parse::{{closure}}`.
+	Full,
+	
+	/// The raw symbol name, including its hash, like `_ZN3app5parse17h…E`.
+	Mangled,
+}
+
+static SYMBOL_STYLE: RwLock<SymbolStyle> = RwLock::new(SymbolStyle::Short);
+
+/// Sets how function names are shown by [`set_show_function`]. Defaults to
+/// [`SymbolStyle::Short`].
+/// 
+/// Whether generic arguments appear in full or mangled names depends on the
+/// compiler's symbol mangling scheme, as the default (legacy) scheme omits
+/// them. Raw names are only kept while both settings are enabled, and fall back
+/// to the full name if unavailable.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::{trace_to, SymbolStyle};
+/// use std::fmt::Debug;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// fn describe<T: Debug>(out: &mut Vec<u8>, value: T) {
+///     trace_to!(out, "{value:?}").unwrap();
+/// }
+/// 
+/// trace::set_show_function(true);
+/// let mut names = Vec::new();
+/// for style in [SymbolStyle::Short, SymbolStyle::Full, SymbolStyle::Mangled] {
+///     trace::set_symbol_style(style);
+///     let mut out = Vec::new();
+///     describe(&mut out, 1);
+///     let line = String::from_utf8(out).unwrap();
+///     names.push(line.split(['[', ']']).nth(1).unwrap_or_default().to_owned());
+/// }
+/// 
+/// # if cfg!(feature = "trace") {
+/// assert_eq!(names[0], "describe");
+/// assert!(names[1].contains("::") && names[1].ends_with("describe"));
+/// assert!(names[2].contains("describe") && !names[2].contains("::"));
+/// # }
+/// ```
+pub fn set_symbol_style(style: SymbolStyle) {
+	*write_lock(&SYMBOL_STYLE) = style;
+}
+
+/// Returns how function names are shown. See [`set_symbol_style`].
+pub fn symbol_style() -> SymbolStyle {
+	*read_lock(&SYMBOL_STYLE)
+}
+
+static SHOW_RECURSION: AtomicBool = AtomicBool::new(false);
+
+/// Enables or disables prefixing each message with how many times its function
+/// directly recurses, like `↻3`. Disabled by default.
+/// 
+/// The count is the number of consecutive frames at the top of the call stack
+/// calling the same function, so it's only shown for a function called by
+/// itself. This tells self-recursion apart from a deep chain of distinct calls.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace_to;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// fn s(out: &mut Vec<u8>, n: u8, k: u8) -> u8 {
+///     trace_to!(out, "n:{n}, k:{k}").unwrap();
+///     if n == k {
+///         return 1
+///     }
+///     if k == 0 || n < k {
+///         return 0
+///     }
+///     s(out, n-1, k-1) + s(out, n-1, k)*k
+/// }
+/// 
+/// trace::set_show_recursion(true);
+/// let mut out = Vec::new();
+/// s(&mut out, 3, 2);
+/// 
+/// # if cfg!(feature = "trace") {
+/// let out = String::from_utf8(out).unwrap();
+/// let messages: Vec<&str> = out.lines()
+///     .map(|line| line.split("|   ").last().unwrap())
+///     .collect();
+/// assert_eq!(messages, [
+///     "n:3, k:2",
+///     "↻2 n:2, k:1",
+///     "↻3 n:1, k:0",
+///     "↻3 n:1, k:1",
+///     "↻2 n:2, k:2",
+/// ]);
+/// # }
+/// ```
+pub fn set_show_recursion(enabled: bool) {
+	SHOW_RECURSION.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns whether recursion counts are shown. See [`set_show_recursion`].
+pub fn show_recursion() -> bool {
+	SHOW_RECURSION.load(Ordering::Relaxed)
+}
+
+static SAMPLE_RATE: AtomicUsize = AtomicUsize::new(1);
+
+/// Sets how often traces are printed, as one out of every `rate` traces made by
+/// each thread. Defaults to 1, printing every trace.
+/// 
+/// The first trace of each thread is printed, then the calls in between are
+/// skipped before their message is formatted or their call stack is captured,
+/// as if tracing was disabled. This is meant for hot loops, where occasional
+/// samples are enough, and intentionally leaves gaps in the tree. Only traces
+/// that are enabled and not filtered count towards the rate. A rate of 0 is
+/// the same as 1.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// let lines = trace::capture_session(|| {
+///     trace::set_sample_rate(3);
+///     for i in 0..9 {
+///         trace!("i:{i}");
+///     }
+/// });
+/// 
+/// # if cfg!(feature = "trace") {
+/// assert_eq!(lines.len(), 3);
+/// assert!(lines[0].ends_with("i:0") && lines[1].ends_with("i:3") && lines[2].ends_with("i:6"));
+/// # }
+/// ```
+pub fn set_sample_rate(rate: usize) {
+	SAMPLE_RATE.store(rate, Ordering::Relaxed);
+}
+
+/// Returns how often traces are printed. See [`set_sample_rate`].
+pub fn sample_rate() -> usize {
+	SAMPLE_RATE.load(Ordering::Relaxed).max(1)
+}
+
+static HISTORY: AtomicUsize = AtomicUsize::new(0);
+
+/// Sets how many distinct call stacks traced before the last one are compared
+/// against each trace, per thread. Defaults to 0, comparing only to the last.
+/// 
+/// Depths where the call stack matches any of these are indented as matched,
+/// so returning to a call path seen recently doesn't mark it as diverged
+/// (`>---`) again. Each stack in the history costs a comparison per trace, so
+/// this is best kept small.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace_to;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// fn read(out: &mut Vec<u8>) {
+///     parse(out);
+/// }
+/// fn parse(out: &mut Vec<u8>) {
+///     trace_to!(out, "parse").unwrap();
+/// }
+/// fn write(out: &mut Vec<u8>) {
+///     trace_to!(out, "write").unwrap();
+/// }
+/// 
+/// let mut out = Vec::new();
+/// for _ in 0..2 {
+///     read(&mut out);
+///     write(&mut out);
+/// }
+/// trace::set_history(1);
+/// for _ in 0..2 {
+///     read(&mut out);
+///     write(&mut out);
+/// }
+/// 
+/// # if cfg!(feature = "trace") {
+/// assert_eq!(String::from_utf8(out).unwrap(), "\
+/// @--->--->---|   parse
+///         |   write
+///         >---|   parse
+///         |   write
+///         >---|   parse
+///         |   write
+///             |   parse
+///         |   write
+/// ");
+/// # }
+/// ```
+pub fn set_history(size: usize) {
+	HISTORY.store(size, Ordering::Relaxed);
+}
+
+/// Returns how many prior call stacks are compared against each trace. See
+/// [`set_history`].
+pub fn history() -> usize {
+	HISTORY.load(Ordering::Relaxed)
+}
+
+static REANCHOR_ON_CRATE_CHANGE: AtomicBool = AtomicBool::new(false);
+
+/// Enables or disables moving the baseline when a trace is made within another
+/// crate than the last baseline. Disabled by default.
+/// 
+/// Normally, the baseline only moves when the call stack diverges below it, so
+/// the traces of a library called by a traced crate are indented relative to
+/// the caller's baseline (see [`set_baseline_crate`]). While enabled, the
+/// baseline moves to the first frame of the tracing crate above the old
+/// baseline instead, giving each crate's traces a fresh root.
+/// 
+/// # Examples
+/// 
+/// ```
+/// # #[cfg(feature = "test-support")] {
+/// use trace::trace_frames;
+/// 
+/// let mut out = Vec::new();
+/// let mut trace = |frames: &[&str], module_path, text| {
+///     trace_frames(&mut out, frames, module_path, text).unwrap();
+/// };
+/// trace::set_reanchor_on_crate_change(true);
+/// trace(&["app::main", "app::run"], "app", "run");
+/// trace(&["app::main", "app::run", "lib::parse", "lib::next"], "lib", "next");
+/// trace(&["app::main", "app::run", "lib::parse"], "lib", "parsed");
+/// trace(&["app::main", "app::run"], "app", "done");
+/// 
+/// assert_eq!(String::from_utf8(out).unwrap(), "\
+/// @---|   run
+/// @---|   next
+/// |   parsed
+/// @---|   done
+/// ");
+/// # }
+/// ```
+pub fn set_reanchor_on_crate_change(enabled: bool) {
+	REANCHOR_ON_CRATE_CHANGE.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns whether the baseline moves between crates. See
+/// [`set_reanchor_on_crate_change`].
+pub fn reanchor_on_crate_change() -> bool {
+	REANCHOR_ON_CRATE_CHANGE.load(Ordering::Relaxed)
+}
+
+static FIXED_BASELINE: RwLock<Option<usize>> = RwLock::new(None);
+
+/// Pins the baseline to a fixed depth of the call stack, or `None` to let it
+/// move (the default).
+/// 
+/// The depth counts frames from the bottom of the call stack, like the
+/// `depth` of a [`TraceRecord`]. While pinned, the baseline never moves, even
+/// if the call stack diverges below it or [`trace_anchor!`] was called, so
+/// every trace is indented relative to the same level for the whole run. A
+/// trace at or below the pinned depth isn't indented.
+/// 
+/// [`trace_anchor!`]: crate::trace_anchor
+/// 
+/// # Examples
+/// 
+/// ```
+/// # #[cfg(feature = "test-support")] {
+/// use trace::trace_frames;
+/// 
+/// let mut out = Vec::new();
+/// let mut trace = |frames: &[&str], text| {
+///     trace_frames(&mut out, frames, "app", text).unwrap();
+/// };
+/// trace::set_fixed_baseline(Some(1));
+/// trace(&["std::rt", "app::main"], "main");
+/// trace(&["std::rt", "app::main", "app::run"], "run");
+/// trace(&["std::rt", "std::hook", "app::on_exit"], "exit");
+/// trace(&["std::thread", "std::spawn", "app::work"], "thread");
+/// trace(&["std::thread"], "bottom");
+/// 
+/// assert_eq!(String::from_utf8(out).unwrap(), "\
+/// @   main
+/// >---|   run
+/// >---|   exit
+/// @---|   thread
+/// @   bottom
+/// ");
+/// # }
+/// ```
+pub fn set_fixed_baseline(depth: Option<usize>) {
+	*write_lock(&FIXED_BASELINE) = depth;
+}
+
+/// Returns the depth that the baseline is pinned to. See
+/// [`set_fixed_baseline`].
+pub fn fixed_baseline() -> Option<usize> {
+	*read_lock(&FIXED_BASELINE)
+}
+
+static JSON: AtomicBool = AtomicBool::new(false);
+static IS_JSON_SET: AtomicBool = AtomicBool::new(false);
+
+/// Enables or disables printing each trace as a JSON object instead of an
+/// indented line. Disabled by default.
+/// 
+/// Each object has the fields `depth`, `match_depth`, `basis_depth`,
+/// `module_path`, `thread`, and `message`. The depths count frames from the
+/// bottom of the call stack, so the indentation level of a trace is `depth -
+/// basis_depth`. The depths are `null` if the call stack couldn't be captured,
+/// and `thread` is `null` for unnamed threads. Line prefixes, like timestamps,
+/// are not included.
+/// 
+/// This can also be set by the `TRACE_FORMAT` environment variable, as `json`
+/// or `tree`, which is read once at the first trace and is ignored if this
+/// function was called first, like `TRACE_DISABLE` for [`set_enabled`]. Other
+/// values are ignored.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace_to;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// fn count_down(out: &mut Vec<u8>, n: u8) {
+///     trace_to!(out, "n:{n}").unwrap();
+///     if n > 0 {
+///         count_down(out, n - 1);
+///     }
+/// }
+/// 
+/// // The environment variable doesn't override an explicit call:
+/// std::env::set_var("TRACE_FORMAT", "tree");
+/// trace::set_json(true);
+/// let mut out = Vec::new();
+/// count_down(&mut out, 2);
+/// 
+/// fn field<'a>(line: &'a str, key: &str) -> &'a str {
+///     let start = line.find(&format!("\"{key}\":")).unwrap() + key.len() + 3;
+///     let end = start + line[start..].find([',', '}']).unwrap();
+///     &line[start..end]
+/// }
+/// 
+/// # if cfg!(feature = "trace") {
+/// for (line, n) in String::from_utf8(out).unwrap().lines().zip([2, 1, 0]) {
+///     let depth: usize = field(line, "depth").parse().unwrap();
+///     let basis_depth: usize = field(line, "basis_depth").parse().unwrap();
+///     assert_eq!(depth - basis_depth, 4 - n);
+///     assert_eq!(field(line, "thread"), "\"main\"");
+///     assert_eq!(field(line, "message"), format!("\"n:{n}\""));
+/// }
+/// # }
+/// ```
+/// 
+/// Using the environment variable:
+/// 
+/// ```
+/// use trace::trace_to;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// std::env::set_var("TRACE_FORMAT", "json");
+/// 
+/// let mut out = Vec::new();
+/// trace_to!(&mut out, "hello").unwrap();
+/// 
+/// # if cfg!(feature = "trace") {
+/// let out = String::from_utf8(out).unwrap();
+/// assert!(out.starts_with("{\"depth\":"));
+/// assert!(out.ends_with("\"message\":\"hello\"}\n"));
+/// # }
+/// ```
+pub fn set_json(enabled: bool) {
+	JSON.store(enabled, Ordering::Relaxed);
+	IS_JSON_SET.store(true, Ordering::Relaxed);
+}
+
+/// Returns whether traces are printed as JSON. See [`set_json`].
+pub fn json() -> bool {
+	JSON.load(Ordering::Relaxed)
+}
+
+static MESSAGE_FIRST: AtomicBool = AtomicBool::new(false);
+
+/// Enables or disables printing each message before its depths, instead of
+/// after its indentation. Disabled by default.
+/// 
+/// Each line starts with the message, followed by ` [d=4 b=1]`, where `d` and
+/// `b` are the `depth` and `basis_depth` fields described by [`set_json`]. This
+/// gives up the visual tree so that lines can be searched by their content,
+/// like with `grep '^parse'`. Line prefixes, like timestamps, still come first.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace_to;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// fn count_down(out: &mut Vec<u8>, n: u8) {
+///     trace_to!(out, "n:{n}").unwrap();
+///     if n > 0 {
+///         count_down(out, n - 1);
+///     }
+/// }
+/// 
+/// trace::set_message_first(true);
+/// let mut out = Vec::new();
+/// count_down(&mut out, 2);
+/// 
+/// # if cfg!(feature = "trace") {
+/// let out = String::from_utf8(out).unwrap();
+/// let depths: Vec<(usize, usize)> = out.lines().zip([2, 1, 0]).map(|(line, n)| {
+///     let (message, depths) = line.split_once(" [d=").unwrap();
+///     assert_eq!(message, format!("n:{n}"));
+///     let (depth, basis_depth) = depths.strip_suffix(']').unwrap().split_once(" b=").unwrap();
+///     (depth.parse().unwrap(), basis_depth.parse().unwrap())
+/// }).collect();
+/// assert_eq!(depths.len(), 3);
+/// assert_eq!(depths[1], (depths[0].0 + 1, depths[0].1));
+/// assert_eq!(depths[2], (depths[0].0 + 2, depths[0].1));
+/// # }
+/// ```
+pub fn set_message_first(enabled: bool) {
+	MESSAGE_FIRST.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns whether messages are printed before their depths. See
+/// [`set_message_first`].
+pub fn message_first() -> bool {
+	MESSAGE_FIRST.load(Ordering::Relaxed)
+}
+
+/// Clears the current thread's trace state, so the next trace starts at a
+/// fresh baseline rather than being compared against the previous one.
+/// 
+/// This is the supported way to separate logical phases of a program, since
+/// otherwise the first trace of a phase is indented relative to the last trace
+/// of the phase before it. See [`reset_with_summary`] to also print a summary
+/// of the phase.
+/// 
+/// The summary of a run of repeated lines is printed first, if repeats are
+/// collapsed, ignoring any error. See [`set_collapse_repeats`].
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace_to;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// fn phase(out: &mut Vec<u8>, name: &str) {
+///     trace_to!(out, "{name}").unwrap();
+/// }
+/// 
+/// let mut out = Vec::new();
+/// phase(&mut out, "one");
+/// phase(&mut out, "two");
+/// trace::reset();
+/// phase(&mut out, "three");
+/// 
+/// # if cfg!(feature = "trace") {
+/// let out = String::from_utf8(out).unwrap();
+/// let lines: Vec<&str> = out.lines().collect();
+/// assert_eq!(lines[0], "@--->---|   one");
+/// assert!(!lines[1].starts_with('@'));
+/// assert_eq!(lines[2], "@--->---|   three");
+/// # }
+/// ```
+pub fn reset() {
+	#[cfg(feature = "indent")] {
+		end_repeats().ok();
+		STATE.with_borrow_mut(|state| *state = TraceState::new());
+	}
+}
+
+/// Prints a summary of the current thread's traces since the last reset, then
+/// clears its trace state like [`reset`].
+/// 
+/// The summary is one line, like `phase: max depth 3, 4 lines`, giving the most
+/// levels any line was indented by and the number of lines printed (counted
+/// like [`emitted_count`], but per thread). It's printed without indentation
+/// to the same output as [`trace!`], even if tracing is disabled. Nothing is
+/// printed if the `indent` feature is disabled.
+/// 
+/// [`trace!`]: crate::trace
+/// 
+/// # Panics
+/// 
+/// Panics if writing the summary fails.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// fn count_down(n: u8) {
+///     trace!("n:{n}");
+///     if n > 0 {
+///         count_down(n - 1);
+///     }
+/// }
+/// 
+/// let lines = trace::capture_session(|| {
+///     count_down(3);
+///     trace::reset_with_summary();
+///     trace::reset_with_summary();
+/// });
+/// 
+/// # if cfg!(feature = "trace") {
+/// assert_eq!(lines, [
+///     "@--->---|   n:3",
+///     "        >---|   n:2",
+///     "            >---|   n:1",
+///     "                >---|   n:0",
+///     "phase: max depth 5, 4 lines",
+///     "phase: max depth 0, 0 lines",
+/// ]);
+/// # }
+/// ```
+pub fn reset_with_summary() {
+	#[cfg(feature = "indent")] {
+		let (depth, count) = STATE.with_borrow(|state| (state.phase_depth, state.phase_count));
+		let count_unit = if count == 1 { "line" } else { "lines" };
+		let result = write_output(|writer, _| {
+			write_line(writer, format_args!("phase: max depth {depth}, {count} {count_unit}"))
+		});
+		if let Err(e) = result {
+			panic_output(e);
+		}
+	}
+	reset();
+}
+
+/// Returns how many levels [`trace!`] would indent a line traced from the
+/// caller, without printing anything.
+/// 
+/// This captures the call stack and updates the indentation state just like a
+/// trace would, so a following trace is indented relative to this call. It
+/// ignores [`set_enabled`] and the module filters. Returns `None` if the call
+/// stack couldn't be captured, like if `RUST_BACKTRACE` isn't set.
+/// 
+/// [`trace!`]: crate::trace
+/// 
+/// # Examples
+/// 
+/// ```
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// fn recurse(depths: &mut Vec<Option<usize>>, n: u8) {
+///     depths.push(trace::current_depth());
+///     if n > 0 {
+///         recurse(depths, n - 1);
+///     }
+/// }
+/// 
+/// let mut depths = Vec::new();
+/// recurse(&mut depths, 3);
+/// 
+/// # if cfg!(feature = "trace") {
+/// assert!(depths.windows(2).all(|pair| pair[0] < pair[1]));
+/// # }
+/// ```
+/// 
+/// The frames within this crate never count towards the depth, so each macro
+/// is indented the same at the same call site, however its internals are
+/// optimized:
+/// 
+/// ```
+/// use trace::trace_string;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// fn site() -> (Option<usize>, String) {
+///     (trace::current_depth(), trace_string!("x"))
+/// }
+/// 
+/// let (depth, line) = site();
+/// 
+/// # if cfg!(feature = "trace") {
+/// assert_eq!(line, format!("{}|   x", " ".repeat(4 * depth.unwrap())));
+/// # }
+/// ```
+#[inline(never)]
+pub fn current_depth() -> Option<usize> {
+	#[cfg(feature = "indent")]
+	if let Some(depth) = _trace_depth() {
+		return Some(depth)
+	}
+	None
+}
+
+/// Returns the names of the functions in the caller's call stack, from the
+/// bottom up, as [`trace!`] sees them.
+/// 
+/// The frames are trimmed like those of a trace, so the caller is the last one
+/// and no frames within this crate are included. They're demangled without
+/// hashes, like `my_crate::parser::parse`. This is for building custom tools,
+/// and is as expensive as a trace, since it captures the call stack. Returns
+/// an empty list if the call stack couldn't be captured, like if
+/// `RUST_BACKTRACE` isn't set, or if the `trace` feature is disabled.
+/// 
+/// [`trace!`]: crate::trace
+/// 
+/// # Examples
+/// 
+/// ```
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// fn outer() -> (Vec<String>, Vec<String>) {
+///     (trace::capture_frames(), inner())
+/// }
+/// 
+/// fn inner() -> Vec<String> {
+///     trace::capture_frames()
+/// }
+/// 
+/// let (outer_frames, inner_frames) = outer();
+/// 
+/// # if cfg!(feature = "trace") {
+/// assert_eq!(inner_frames.len(), outer_frames.len() + 1);
+/// assert!(outer_frames.last().unwrap().ends_with("::outer"));
+/// assert!(inner_frames.last().unwrap().ends_with("::inner"));
+/// # }
+/// ```
+#[inline(never)]
+pub fn capture_frames() -> Vec<String> {
+	#[cfg(feature = "indent")]
+	if let Some(frames) = capture_stack() {
+		return frames.into_iter().map(|frame| frame.name).collect()
+	}
+	Vec::new()
+}
+
+static EMITTED_COUNT: AtomicU64 = AtomicU64::new(0);
+
+/// Returns the number of trace lines printed so far, by all threads.
+/// 
+/// Every line successfully written by a trace macro counts, including the
+/// closing line of a [`trace_scope!`] and lines printed without indentation
+/// because the call stack couldn't be captured. Traces ignored because tracing
+/// is disabled or filtered, or suppressed by [`trace_once!`] or
+/// [`trace_every!`], don't count. A multiline message counts as one line.
+/// 
+/// [`trace_scope!`]: crate::trace_scope
+/// [`trace_once!`]: crate::trace_once
+/// [`trace_every!`]: crate::trace_every
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace_to;
+/// 
+/// let mut out: Vec<u8> = Vec::new();
+/// for i in 0..3 {
+///     trace_to!(&mut out, "i:{i}").unwrap();
+/// }
+/// trace::set_enabled(false);
+/// trace_to!(&mut out, "ignored").unwrap();
+/// 
+/// # if cfg!(feature = "trace") {
+/// assert_eq!(trace::emitted_count(), 3);
+/// # }
+/// trace::reset_count();
+/// assert_eq!(trace::emitted_count(), 0);
+/// ```
+pub fn emitted_count() -> u64 {
+	EMITTED_COUNT.load(Ordering::Relaxed)
+}
+
+/// Resets the count returned by [`emitted_count`] to 0.
+pub fn reset_count() {
+	EMITTED_COUNT.store(0, Ordering::Relaxed);
+}
+
+#[cfg(feature = "indent")]
+fn count_emitted(depth: Option<usize>) {
+	//! Counts a printed trace line, indented by the given number of levels if
+	//! it's indented. See [`emitted_count`], [`last_emitted`], and
+	//! [`reset_with_summary`].
+	
+	if IS_OUTPUT_TRUNCATED.load(Ordering::Relaxed) {
+		return
+	}
+	EMITTED_COUNT.fetch_add(1, Ordering::Relaxed);
+	LAST_EMITTED.set(true);
+	STATE.with(|state| {
+		if let Ok(mut state) = state.try_borrow_mut() {
+			state.phase_count += 1;
+			state.phase_depth = state.phase_depth.max(depth.unwrap_or(0));
+		}
+	});
+}
+
+static MAX_OUTPUT_BYTES: AtomicUsize = AtomicUsize::new(usize::MAX);
+static OUTPUT_BYTES: AtomicUsize = AtomicUsize::new(0);
+static IS_OUTPUT_TRUNCATED: AtomicBool = AtomicBool::new(false);
+
+/// Sets the total number of bytes of trace output that may be printed, by all
+/// threads. Unlimited (`usize::MAX`) by default.
+/// 
+/// Each line counts its bytes, including the newline and any prefix, as it's
+/// written. Once a line would exceed the limit, it's replaced by a single
+/// `trace output truncated` notice and no more trace lines are printed, while
+/// the program itself continues. Lines that aren't printed aren't counted by
+/// [`emitted_count`]. Setting the limit restarts the count of bytes, so it can
+/// also be used to resume output after truncation.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace_to;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// trace::set_max_output_bytes(40);
+/// let mut out = Vec::new();
+/// for i in 0..100 {
+///     trace_to!(&mut out, "i:{i}").unwrap();
+/// }
+/// 
+/// # if cfg!(feature = "trace") {
+/// assert_eq!(String::from_utf8(out).unwrap(), "\
+/// @---|   i:0
+///     |   i:1
+///     |   i:2
+/// trace output truncated
+/// ");
+/// assert_eq!(trace::emitted_count(), 3);
+/// # }
+/// ```
+/// 
+/// A sink receives the notice as a plain line, after the records of the lines
+/// that fit:
+/// 
+/// ```
+/// use trace::{trace, TraceRecorder};
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// trace::set_max_output_bytes(40);
+/// let recorder = TraceRecorder::new();
+/// trace::set_sink(Box::new(recorder.clone()));
+/// for i in 0..100 {
+///     trace!("i:{i}");
+/// }
+/// trace::clear_sink();
+/// 
+/// # if cfg!(feature = "trace") {
+/// let messages: Vec<String> = recorder.into_tree().children.into_iter()
+///     .map(|node| node.message)
+///     .collect();
+/// assert_eq!(messages, ["i:0", "i:1", "i:2", "trace output truncated"]);
+/// # }
+/// ```
+pub fn set_max_output_bytes(max_bytes: usize) {
+	MAX_OUTPUT_BYTES.store(max_bytes, Ordering::Relaxed);
+	OUTPUT_BYTES.store(0, Ordering::Relaxed);
+	IS_OUTPUT_TRUNCATED.store(false, Ordering::Relaxed);
+}
+
+/// Returns the total number of bytes of trace output that may be printed. See
+/// [`set_max_output_bytes`].
+pub fn max_output_bytes() -> usize {
+	MAX_OUTPUT_BYTES.load(Ordering::Relaxed)
+}
+
+/// The line printed in place of the first line exceeding the output limit. See
+/// [`set_max_output_bytes`].
+#[cfg(feature = "indent")]
+const TRUNCATION_NOTICE: &str = "trace output truncated";
+
+#[cfg(feature = "indent")]
+fn write_line<W>(writer: &mut W, line: std::fmt::Arguments) -> io::Result<()>
+where
+	W: io::Write + ?Sized
+{
+	//! Writes a line of trace output, or the truncation notice in place of the
+	//! first line exceeding the limit. See [`set_max_output_bytes`].
+	
+	let max_bytes = max_output_bytes();
+	if max_bytes == usize::MAX {
+		return writeln!(writer, "{line}")
+	}
+	if IS_OUTPUT_TRUNCATED.load(Ordering::Relaxed) {
+		return Ok(())
+	}
+	
+	 // Count Bytes Against Limit:
+	let line = format!("{line}\n");
+	let total_bytes = OUTPUT_BYTES.fetch_add(line.len(), Ordering::Relaxed)
+		.saturating_add(line.len());
+	if total_bytes <= max_bytes {
+		return writer.write_all(line.as_bytes())
+	}
+	
+	 // Replace With Notice Once:
+	if IS_OUTPUT_TRUNCATED.swap(true, Ordering::Relaxed) {
+		return Ok(())
+	}
+	writeln!(writer, "{TRUNCATION_NOTICE}")
+}
+
+/// Returns whether the last trace macro called on this thread printed a line.
+/// 
+/// This is false if the trace was disabled, filtered, or suppressed (like by
+/// [`set_sample_rate`]), or if writing it failed. Lines printed without
+/// indentation because the call stack couldn't be captured still count, as
+/// they do for [`emitted_count`]. Always false if the `indent` feature is
+/// disabled.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace_to;
+/// 
+/// let mut out: Vec<u8> = Vec::new();
+/// trace::set_enabled(false);
+/// trace_to!(&mut out, "ignored").unwrap();
+/// assert!(!trace::last_emitted());
+/// 
+/// trace::set_enabled(true);
+/// trace_to!(&mut out, "shown").unwrap();
+/// # if cfg!(feature = "trace") {
+/// assert!(trace::last_emitted());
+/// # }
+/// ```
+pub fn last_emitted() -> bool {
+	#[cfg(feature = "indent")]
+	if LAST_EMITTED.get() {
+		return true
+	}
+	false
+}
+
+/// Returns whether the call stack of the last trace macro called on this thread
+/// was captured without any frames, so its line was printed without
+/// indentation.
+/// 
+/// This happens on platforms the `backtrace` crate can't unwind, or with a
+/// [`set_frame_provider`] provider that returns no frames. It isn't reported by
+/// [`try_trace!`], since the line is still printed. Always false if the
+/// `indent` feature is disabled.
+/// 
+/// [`try_trace!`]: crate::try_trace
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::try_trace;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// let lines = trace::capture_session(|| {
+///     trace::set_frame_provider(Box::new(Vec::new));
+///     assert!(try_trace!("unindented").is_ok());
+///     trace::clear_frame_provider();
+/// });
+/// 
+/// # if cfg!(feature = "indent") {
+/// assert!(trace::last_capture_failed());
+/// assert_eq!(lines, ["unindented"]);
+/// # }
+/// 
+/// try_trace!("indented").unwrap();
+/// assert!(!trace::last_capture_failed());
+/// ```
+pub fn last_capture_failed() -> bool {
+	#[cfg(feature = "indent")]
+	if IS_CAPTURE_FAILED.get() {
+		return true
+	}
+	false
+}
+
+static MAX_DEPTH: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+/// Sets the maximum number of indentation levels shown before the current
+/// depth. Unbounded by default.
+/// 
+/// Deeper levels are collapsed into a leading `…` marker labeled with the real
+/// depth, like `…12 `, to keep the prefix of deeply nested traces bounded. Only
+/// the printed indentation is affected. A maximum of 0 is clamped to 1.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace_to;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// fn count_down(out: &mut Vec<u8>, n: u8) {
+///     trace_to!(out, "n:{n}").unwrap();
+///     if n > 0 {
+///         count_down(out, n - 1);
+///     }
+/// }
+/// 
+/// trace::set_max_depth(3);
+/// let mut out = Vec::new();
+/// count_down(&mut out, 3);
+/// 
+/// # if cfg!(feature = "trace") {
+/// assert_eq!(String::from_utf8(out).unwrap(), "\
+/// @--->---|   n:3
+///         >---|   n:2
+/// …4      >---|   n:1
+/// …5      >---|   n:0
+/// ");
+/// # }
+/// ```
+pub fn set_max_depth(depth: usize) {
+	MAX_DEPTH.store(depth.max(1), Ordering::Relaxed);
+}
+
+/// Returns the maximum indentation depth. See [`set_max_depth`].
+pub fn max_depth() -> usize {
+	MAX_DEPTH.load(Ordering::Relaxed)
+}
+
+static DEPTH_WINDOW: RwLock<Option<(usize, usize)>> = RwLock::new(None);
+
+/// Sets the inclusive range of indentation levels that traces are printed at,
+/// or `None` to print traces at any level (the default).
+/// 
+/// Traces outside of the window aren't printed at all, unlike with
+/// [`set_max_depth`], but they still update the indentation state, so lines
+/// re-entering the window are indented consistently. The level of a trace is
+/// how far it's indented from the baseline, not counting groups (see
+/// [`trace_group!`]).
+/// 
+/// [`trace_group!`]: crate::trace_group
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace_to;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// fn count_down(out: &mut Vec<u8>, n: u8) {
+///     trace_to!(out, "n:{n}").unwrap();
+///     if n > 0 {
+///         count_down(out, n - 1);
+///     }
+/// }
+/// 
+/// trace::set_depth_window(Some((3, 6)));
+/// let mut out = Vec::new();
+/// count_down(&mut out, 8);
+/// 
+/// # if cfg!(feature = "trace") {
+/// assert_eq!(String::from_utf8(out).unwrap().lines().count(), 4);
+/// # }
+/// ```
+pub fn set_depth_window(window: Option<(usize, usize)>) {
+	*write_lock(&DEPTH_WINDOW) = window;
+}
+
+/// Returns the range of indentation levels that traces are printed at. See
+/// [`set_depth_window`].
+pub fn depth_window() -> Option<(usize, usize)> {
+	*read_lock(&DEPTH_WINDOW)
+}
+
+static MODULE_FILTER: RwLock<Option<Vec<String>>> = RwLock::new(None);
+
+/// Sets the modules that traces are printed from, or `None` to allow all
+/// modules (the default).
+/// 
+/// A trace is printed only if the module it was called from is, or is within,
+/// one of the given module paths (e.g. `my_crate::parser`). Traces from other
+/// modules are ignored entirely, as if they were never called, so printed
+/// lines are indented relative to the last printed line.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace_to;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// mod filtered {
+///     pub fn run(out: &mut Vec<u8>) {
+///         trace::trace_to!(out, "filtered").unwrap();
+///         allowed::run(out);
+///     }
+///     
+///     pub mod allowed {
+///         pub fn run(out: &mut Vec<u8>) {
+///             trace::trace_to!(out, "allowed").unwrap();
+///         }
+///     }
+/// }
+/// 
+/// let allowed = format!("{}::filtered::allowed", module_path!());
+/// trace::set_module_filter(Some(vec![allowed]));
+/// let mut out = Vec::new();
+/// filtered::run(&mut out);
+/// filtered::allowed::run(&mut out);
+/// 
+/// # if cfg!(feature = "trace") {
+/// let out = String::from_utf8(out).unwrap();
+/// let lines: Vec<&str> = out.lines().collect();
+/// assert_eq!(lines[0], "@--->--->---|   allowed");
+/// assert!(lines[1].ends_with("|   allowed"));
+/// assert!(!out.contains("filtered"));
+/// # }
+/// ```
+pub fn set_module_filter(modules: Option<Vec<String>>) {
+	*write_lock(&MODULE_FILTER) = modules;
+}
+
+static MODULE_BLOCKLIST: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+/// Sets the modules that traces are never printed from. Empty by default.
+/// 
+/// A trace is ignored if the module it was called from is, or is within, one
+/// of the given module paths. Like with [`set_module_filter`], ignored traces
+/// are treated as if they were never called, so they don't affect the
+/// indentation of other lines.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace_to;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// mod noisy {
+///     pub fn run(out: &mut Vec<u8>) {
+///         trace::trace_to!(out, "noisy").unwrap();
+///     }
+/// }
+/// 
+/// mod quiet {
+///     pub fn run(out: &mut Vec<u8>) {
+///         trace::trace_to!(out, "quiet").unwrap();
+///     }
+/// }
+/// 
+/// trace::set_module_blocklist(vec![format!("{}::noisy", module_path!())]);
+/// let mut out = Vec::new();
+/// noisy::run(&mut out);
+/// quiet::run(&mut out);
+/// noisy::run(&mut out);
+/// 
+/// # if cfg!(feature = "trace") {
+/// assert_eq!(String::from_utf8(out).unwrap(), "@--->---|   quiet\n");
+/// # }
+/// ```
+pub fn set_module_blocklist(modules: Vec<String>) {
+	*write_lock(&MODULE_BLOCKLIST) = modules;
+}
+
+static MODULE_OFFSETS: RwLock<Vec<(String, usize)>> = RwLock::new(Vec::new());
+
+/// Sets a number of extra indentation levels for traces from a module. None
+/// are set by default.
+/// 
+/// The levels are added as blank space after a trace's own symbol, like an
+/// open [`trace_group!`], to every trace whose module is, or is within, the
+/// given module path. Offsets of nested modules add up, so `app` and
+/// `app::internal` with an offset of 1 each shift traces from the latter by 2.
+/// Setting a module's offset again replaces it, and an offset of 0 removes it.
+/// The offset only changes how lines are printed, not how the call stack is
+/// compared to the next trace's.
+/// 
+/// [`trace_group!`]: crate::trace_group
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace_to;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// mod app {
+///     pub fn run(out: &mut Vec<u8>) {
+///         trace::trace_to!(out, "app").unwrap();
+///         framework::poll(out);
+///     }
+///     
+///     pub mod framework {
+///         pub fn poll(out: &mut Vec<u8>) {
+///             trace::trace_to!(out, "framework").unwrap();
+///         }
+///     }
+/// }
+/// 
+/// trace::set_module_offset(format!("{}::app::framework", module_path!()), 2);
+/// let mut out = Vec::new();
+/// app::run(&mut out);
+/// 
+/// # if cfg!(feature = "trace") {
+/// assert_eq!(String::from_utf8(out).unwrap(), "\
+/// @--->---|   app
+///         >---|           framework
+/// ");
+/// # }
+/// ```
+pub fn set_module_offset(module: impl Into<String>, extra_levels: usize) {
+	let module = module.into();
+	let mut module_offsets = write_lock(&MODULE_OFFSETS);
+	module_offsets.retain(|(offset_module, _)| *offset_module != module);
+	if extra_levels > 0 {
+		module_offsets.push((module, extra_levels));
+	}
+}
+
+/// Returns the total number of extra indentation levels for traces from a
+/// module. See [`set_module_offset`].
+pub fn module_offset(module_path: &str) -> usize {
+	read_lock(&MODULE_OFFSETS).iter()
+		.filter(|(module, _)| is_within_module(module_path, module))
+		.fold(0, |total, (_, extra_levels)| total.saturating_add(*extra_levels))
+}
+
+static COLLAPSE_REPEATS: AtomicBool = AtomicBool::new(false);
+
+/// Enables or disables collapsing runs of identical lines. Disabled by default.
+/// 
+/// While enabled, a line printed by [`trace!`] that's identical to the one
+/// before it, including its indentation, isn't printed. Instead, once a
+/// different line is traced, the repeated line is printed again with its
+/// original prefix and the length of the run, like ` (x42)`. The last run is
+/// also ended by [`flush`], [`reset`], or disabling this, which ignore any
+/// error printing it. Collapsed lines still count toward [`emitted_count`].
+/// 
+/// Only lines printed to the output of [`trace!`] are collapsed, not those
+/// written elsewhere by macros like [`trace_to!`] and [`trace_string!`] or
+/// printed by [`peek_depth!`], and those don't interrupt a run.
+/// 
+/// [`trace!`]: crate::trace
+/// [`trace_to!`]: crate::trace_to
+/// [`trace_string!`]: crate::trace_string
+/// [`peek_depth!`]: crate::peek_depth
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::{trace, trace_to};
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// trace::set_collapse_repeats(true);
+/// let mut out = Vec::new();
+/// let lines = trace::capture_session(|| {
+///     trace!("start");
+///     for _ in 0..5 {
+///         trace!("tick");
+///         trace_to!(&mut out, "tock").unwrap();
+///     }
+/// });
+/// 
+/// # if cfg!(feature = "trace") {
+/// assert_eq!(lines, [
+///     "@---|   start",
+///     "    |   tick",
+///     "    |   tick (x5)",
+/// ]);
+/// assert_eq!(String::from_utf8(out).unwrap().lines().count(), 5);
+/// # }
+/// ```
+pub fn set_collapse_repeats(enabled: bool) {
+	COLLAPSE_REPEATS.store(enabled, Ordering::Relaxed);
+	#[cfg(feature = "indent")]
+	if !enabled {
+		end_repeats().ok();
+	}
+}
+
+/// Returns whether repeated lines are collapsed. See [`set_collapse_repeats`].
+pub fn collapse_repeats() -> bool {
+	COLLAPSE_REPEATS.load(Ordering::Relaxed)
+}
+
+static ESCAPE_MARKERS: AtomicBool = AtomicBool::new(false);
+
+/// Enables or disables escaping indentation markers in messages. Disabled by
+/// default.
+/// 
+/// While enabled, the characters that begin each indentation symbol (`@`, `>`,
+/// and `|` by default, see [`set_config`]) are escaped with a backslash in the
+/// message, as are backslashes, so that the message can't be mistaken for the
+/// indentation. JSON output isn't escaped.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace_to;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// trace::set_escape_markers(true);
+/// let mut out = Vec::new();
+/// trace_to!(&mut out, ">---|   not a level").unwrap();
+/// 
+/// # if cfg!(feature = "trace") {
+/// assert_eq!(String::from_utf8(out).unwrap(), "@---|   \\>---\\|   not a level\n");
+/// # }
+/// ```
+pub fn set_escape_markers(enabled: bool) {
+	ESCAPE_MARKERS.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns whether markers in messages are escaped. See [`set_escape_markers`].
+pub fn escape_markers() -> bool {
+	ESCAPE_MARKERS.load(Ordering::Relaxed)
+}
+
+static FORCE_CAPTURE: AtomicBool = AtomicBool::new(false);
+
+/// Enables or disables capturing the call stack regardless of the
+/// `RUST_BACKTRACE` and `RUST_LIB_BACKTRACE` environment variables. Disabled
+/// by default.
+/// 
+/// While enabled, traces are indented even if neither variable is set (or if
+/// either is `0`), like [`std::backtrace::Backtrace::force_capture`]. This
+/// is useful in tests and programs where the environment can't be set, but
+/// every trace then pays the cost of capturing and resolving the call stack,
+/// which is much slower than just printing the line.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace_to;
+/// std::env::remove_var("RUST_BACKTRACE");
+/// std::env::remove_var("RUST_LIB_BACKTRACE");
+/// 
+/// fn helper(out: &mut Vec<u8>) {
+///     trace_to!(out, "indented").unwrap();
+/// }
+/// 
+/// trace::force_capture(true);
+/// let mut out = Vec::new();
+/// helper(&mut out);
+/// 
+/// # if cfg!(feature = "trace") {
+/// assert_eq!(String::from_utf8(out).unwrap(), "@--->---|   indented\n");
+/// # }
+/// ```
+pub fn force_capture(enabled: bool) {
+	FORCE_CAPTURE.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns whether the call stack is captured regardless of the environment.
+/// See [`force_capture`].
+pub fn capture_forced() -> bool {
+	FORCE_CAPTURE.load(Ordering::Relaxed)
+}
+
+type FrameProvider = dyn Fn() -> Vec<String> + Send + Sync;
+
+static FRAME_PROVIDER: RwLock<Option<Arc<FrameProvider>>> = RwLock::new(None);
+
+/// Indents traces by the call stack returned from the given function, in place
+/// of capturing it, until [`clear_frame_provider`].
+/// 
+/// The function returns the names of the functions on the call stack from the
+/// bottom up, like `["app::main", "app::run"]`, where the last is the one
+/// tracing. It's called for every trace on the tracing thread, regardless of
+/// the `RUST_BACKTRACE` and `RUST_LIB_BACKTRACE` environment variables, so
+/// traces can be indented where capturing the call stack isn't possible (like
+/// WebAssembly) or with manually tracked frames. The baseline is found from
+/// the names as usual, so [`set_baseline_crate`] may be needed if they aren't
+/// in the tracing crate. An empty call stack isn't indented.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace_to;
+/// use std::sync::Mutex;
+/// 
+/// static STACK: Mutex<Vec<String>> = Mutex::new(Vec::new());
+/// 
+/// fn enter(name: &str) {
+///     STACK.lock().unwrap().push(format!("app::{name}"));
+/// }
+/// fn exit() {
+///     STACK.lock().unwrap().pop();
+/// }
+/// 
+/// trace::set_frame_provider(Box::new(|| STACK.lock().unwrap().clone()));
+/// trace::set_baseline_crate(Some("app".to_owned()));
+/// let mut out = Vec::new();
+/// enter("main");
+/// trace_to!(&mut out, "start").unwrap();
+/// enter("run");
+/// trace_to!(&mut out, "run").unwrap();
+/// enter("step");
+/// trace_to!(&mut out, "step").unwrap();
+/// exit();
+/// exit();
+/// trace_to!(&mut out, "done").unwrap();
+/// 
+/// # if cfg!(feature = "trace") {
+/// assert_eq!(String::from_utf8(out).unwrap(), "\
+/// @   start
+/// @---|   run
+///     >---|   step
+/// @   done
+/// ");
+/// # }
+/// ```
+pub fn set_frame_provider(provider: Box<FrameProvider>) {
+	*write_lock(&FRAME_PROVIDER) = Some(Arc::from(provider));
+}
+
+/// Removes the function installed by [`set_frame_provider`], capturing the
+/// call stack again.
+pub fn clear_frame_provider() {
+	*write_lock(&FRAME_PROVIDER) = None;
+}
+
+static MARK_FALLBACK: AtomicBool = AtomicBool::new(false);
+
+/// Enables or disables marking lines printed without indentation because the
+/// call stack couldn't be captured. Disabled by default.
+/// 
+/// While enabled, such lines begin with `~ ` (after any prefix), to tell them
+/// apart from traces that just aren't indented. This happens if neither
+/// `RUST_BACKTRACE` nor `RUST_LIB_BACKTRACE` is set (see [`force_capture`]),
+/// or if a trace is made while another is being written on the same thread.
+/// JSON output and lines printed with the `indent` feature disabled aren't
+/// marked.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace_to;
+/// std::env::remove_var("RUST_BACKTRACE");
+/// std::env::remove_var("RUST_LIB_BACKTRACE");
+/// 
+/// trace::set_mark_fallback(true);
+/// let mut out = Vec::new();
+/// trace_to!(&mut out, "no call stack").unwrap();
+/// 
+/// # if cfg!(feature = "trace") {
+/// assert_eq!(String::from_utf8(out).unwrap(), "~ no call stack\n");
+/// # }
+/// ```
+pub fn set_mark_fallback(enabled: bool) {
+	MARK_FALLBACK.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns whether unindented fallback lines are marked. See
+/// [`set_mark_fallback`].
+pub fn mark_fallback() -> bool {
+	MARK_FALLBACK.load(Ordering::Relaxed)
+}
+
+static ASSERT_PANICS: AtomicBool = AtomicBool::new(true);
+
+/// Sets whether a failed [`trace_assert!`] panics after printing its failure
+/// line. Enabled by default.
+/// 
+/// [`trace_assert!`]: crate::trace_assert
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace_assert;
+/// 
+/// trace::set_assert_panics(false);
+/// trace_assert!(1 + 1 == 3, "continued");
+/// 
+/// trace::set_assert_panics(true);
+/// let result = std::panic::catch_unwind(|| trace_assert!(1 + 1 == 3));
+/// assert!(result.is_err());
+/// ```
+pub fn set_assert_panics(enabled: bool) {
+	ASSERT_PANICS.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns whether a failed [`trace_assert!`] panics. See
+/// [`set_assert_panics`].
+/// 
+/// [`trace_assert!`]: crate::trace_assert
+pub fn assert_panics() -> bool {
+	ASSERT_PANICS.load(Ordering::Relaxed)
+}
+
+static WRAP: AtomicUsize = AtomicUsize::new(0);
+
+/// Sets the column to wrap messages at, or `None` to never wrap them (the
+/// default).
+/// 
+/// Long lines of a message are wrapped between words, or within a word if it
+/// doesn't fit on its own, so that each line, including its prefix and
+/// indentation, fits within the given number of columns. The wrapped lines are
+/// indented like the lines of a multiline message. At least one character of
+/// the message is printed per line, however deep the indentation goes. A
+/// column of 0 is the same as `None`.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace_to;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// trace::set_wrap(Some(30));
+/// let mut out = Vec::new();
+/// trace_to!(&mut out, "the quick brown fox jumps over the lazy dog").unwrap();
+/// 
+/// # if cfg!(feature = "trace") {
+/// assert_eq!(String::from_utf8(out).unwrap(), "\
+/// @---|   the quick brown fox
+///     |   jumps over the lazy
+///     |   dog
+/// ");
+/// # }
+/// ```
+pub fn set_wrap(column: Option<usize>) {
+	WRAP.store(column.unwrap_or(0), Ordering::Relaxed);
+}
+
+/// Returns the column messages are wrapped at. See [`set_wrap`].
+pub fn wrap() -> Option<usize> {
+	Some(WRAP.load(Ordering::Relaxed)).filter(|&column| column != 0)
+}
+
+static BASELINE_CRATE: RwLock<Option<String>> = RwLock::new(None);
+
+/// Sets the crate that traces are anchored to, or `None` to anchor each trace
+/// to the crate it's called from (the default).
+/// 
+/// The baseline of a trace is the first function in the call stack from this
+/// crate, so setting it keeps one baseline across a call chain that spans
+/// multiple crates, like those of a workspace.
+/// 
+/// If the crate's name is empty or whitespace, which can also happen with an
+/// unusual module path, the baseline is anchored to the bottom of the call
+/// stack instead.
+/// 
+/// # Examples
+/// 
+/// ```
+/// # #[cfg(feature = "test-support")] {
+/// use trace::trace_frames;
+/// 
+/// let frames = ["std::rt", "app::main", "lib::parse", "lib::token"];
+/// let mut out = Vec::new();
+/// trace_frames(&mut out, &frames, "lib", "default").unwrap();
+/// trace::reset();
+/// trace::set_baseline_crate(Some("app".to_owned()));
+/// trace_frames(&mut out, &frames, "lib", "anchored").unwrap();
+/// 
+/// assert_eq!(String::from_utf8(out).unwrap(), "\
+/// @---|   default
+/// @--->---|   anchored
+/// ");
+/// # }
+/// ```
+pub fn set_baseline_crate(name: Option<String>) {
+	*write_lock(&BASELINE_CRATE) = name;
+}
+
+/// A destination for the lines printed by [`trace!`] and [`trace_scope!`], in
+/// place of the standard output. See [`set_sink`].
+/// 
+/// Implemented for any `Fn(&str) + Send` closure, and by [`TraceRecorder`].
+/// 
+/// [`trace!`]: crate::trace
+/// [`trace_scope!`]: crate::trace_scope
+pub trait TraceSink: Send {
+	/// Receives a finished trace, without its trailing newline. Colors are off.
+	fn write_line(&self, line: &str);
+	
+	/// Receives a finished trace along with its structure, if the trace has
+	/// one. Calls [`write_line`](Self::write_line) by default.
+	fn write_record(&self, record: &TraceRecord, line: &str) {
+		let _ = record;
+		self.write_line(line);
+	}
+	
+	/// Writes out any lines the sink has buffered, returning any error from
+	/// writing them. Called by [`flush`]. Does nothing by default.
+	fn flush(&self) -> std::io::Result<()> {
+		Ok(())
+	}
+}
+
+impl<F> TraceSink for F
+where
+	F: Fn(&str) + Send
+{
+	fn write_line(&self, line: &str) {
+		self(line);
+	}
+}
+
+static SINK: Mutex<Option<Box<dyn TraceSink>>> = Mutex::new(None);
+
+/// Routes the lines printed by [`trace!`], [`try_trace!`], and [`trace_scope!`]
+/// to the given sink instead of the standard output, until [`clear_sink`].
+/// 
+/// Other macros, like [`trace_to!`], keep writing to their own destinations.
+/// Each line is captured and formatted before the sink is locked, so only the
+/// sink itself mustn't trace, since it's called while locked. Lines traced by
+/// different threads at once may reach the sink in either order.
+/// 
+/// [`trace!`]: crate::trace
+/// [`try_trace!`]: crate::try_trace
+/// [`trace_scope!`]: crate::trace_scope
+/// [`trace_to!`]: crate::trace_to
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace;
+/// use std::sync::{Arc, Mutex};
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// fn greet() {
+///     trace!("Hello");
+/// }
+/// 
+/// let lines = Arc::new(Mutex::new(Vec::new()));
+/// let sink_lines = Arc::clone(&lines);
+/// trace::set_sink(Box::new(move |line: &str| {
+///     sink_lines.lock().unwrap().push(line.to_owned());
+/// }));
+/// trace!("start");
+/// greet();
+/// trace::clear_sink();
+/// trace!("printed");
+/// 
+/// # if cfg!(feature = "trace") {
+/// assert_eq!(*lines.lock().unwrap(), ["@---|   start", "    >---|   Hello"]);
+/// # }
+/// ```
+pub fn set_sink(sink: Box<dyn TraceSink>) {
+	*lock(&SINK) = Some(sink);
+}
+
+/// Removes the sink installed by [`set_sink`], printing to the standard output
+/// again.
+pub fn clear_sink() {
+	*lock(&SINK) = None;
+}
+
+/// Runs the given function and returns every line traced while it ran, for
+/// comparing against expected output in snapshot tests.
+/// 
+/// The lines are collected by a sink (see [`set_sink`]) that replaces any
+/// installed sink until the function returns or unwinds. The current thread's
+/// trace state is cleared like [`reset`] before and after, and the baseline is
+/// anchored at `capture_session` (see [`trace_anchor!`]), so the function is
+/// indented as if it were called by a main function, wherever the session is
+/// started. Pair this with [`set_frame_provider`] or [`trace_at!`] for depths
+/// that don't depend on the platform or build. Lines traced by other threads in
+/// the meantime are collected too.
+/// 
+/// [`trace_at!`]: crate::trace_at
+/// [`trace_anchor!`]: crate::trace_anchor
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// fn s(n: u8, k: u8) -> u8 {
+///     trace!("n:{n}, k:{k}");
+///     if n == k {
+///         return 1
+///     }
+///     if k == 0 || n < k {
+///         return 0
+///     }
+///     s(n-1, k-1) + s(n-1, k)*k
+/// }
+/// 
+/// let lines = trace::capture_session(|| {
+///     trace!("# of ways to group 3 items into 2 unordered sets:");
+///     trace!("Result: {}", s(3, 2));
+/// });
+/// 
+/// # if cfg!(feature = "trace") {
+/// assert_eq!(lines, [
+///     "@---|   # of ways to group 3 items into 2 unordered sets:",
+///     "    >---|   n:3, k:2",
+///     "        >---|   n:2, k:1",
+///     "            >---|   n:1, k:0",
+///     "                |   n:1, k:1",
+///     "            |   n:2, k:2",
+///     "    |   Result: 3",
+/// ]);
+/// # }
+/// ```
+#[inline(never)]
+pub fn capture_session<F>(f: F) -> Vec<String>
+where
+	F: FnOnce()
+{
+	struct SessionGuard(Option<Box<dyn TraceSink>>);
+	
+	impl Drop for SessionGuard {
+		fn drop(&mut self) {
+			reset();
+			*lock(&SINK) = self.0.take();
+		}
+	}
+	
+	let (sender, receiver) = std::sync::mpsc::channel();
+	reset();
+	let guard = SessionGuard(lock(&SINK).replace(Box::new(ChannelSink(sender))));
+	#[cfg(feature = "indent")]
+	STATE.with_borrow_mut(|state| state.is_session_pending = true);
+	f();
+	drop(guard);
+	
+	receiver.try_iter().collect()
+}
+
+/// A [`TraceSink`] sending lines over a channel. See [`set_channel_sink`].
+struct ChannelSink(std::sync::mpsc::Sender<String>);
+
+impl TraceSink for ChannelSink {
+	fn write_line(&self, line: &str) {
+		if let Err(std::sync::mpsc::SendError(line)) = self.0.send(line.to_owned()) {
+			println!("{line}");
+		}
+	}
+}
+
+/// Installs a sink (see [`set_sink`]) that sends each line over the given
+/// channel, like to a separate viewer thread.
+/// 
+/// Lines are sent without their trailing newline. If the receiver has been
+/// dropped, lines are printed to the standard output instead, so that they
+/// aren't lost.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// fn greet() {
+///     trace!("Hello");
+/// }
+/// 
+/// let (sender, receiver) = std::sync::mpsc::channel();
+/// trace::set_channel_sink(sender);
+/// trace!("start");
+/// greet();
+/// trace::clear_sink();
+/// 
+/// # if cfg!(feature = "trace") {
+/// let lines: Vec<String> = receiver.iter().collect();
+/// assert_eq!(lines, ["@---|   start", "    >---|   Hello"]);
+/// # }
+/// ```
+pub fn set_channel_sink(sender: std::sync::mpsc::Sender<String>) {
+	set_sink(Box::new(ChannelSink(sender)));
+}
+
+/// A [`TraceSink`] appending lines to a file, rotated by size. See
+/// [`set_file_output`].
+struct FileSink {
+	path: PathBuf,
+	max_bytes: u64,
+	max_files: usize,
+	
+	/// The open file and its current length in bytes, including the lines
+	/// still buffered.
+	file: Mutex<(std::io::BufWriter<std::fs::File>, u64)>,
+	
+	/// The first error while writing, kept until [`flush`].
+	error: Mutex<Option<std::io::Error>>,
+}
+
+impl FileSink {
+	fn open(path: &Path) -> std::io::Result<(std::io::BufWriter<std::fs::File>, u64)> {
+		//! Opens the file for appending, returning it and its length.
+		
+		let file = std::fs::OpenOptions::new()
+			.create(true)
+			.append(true)
+			.open(path)?;
+		let len = file.metadata()?.len();
+		Ok((std::io::BufWriter::new(file), len))
+	}
+	
+	fn rotated_path(&self, index: usize) -> PathBuf {
+		//! Returns the path of a rotated file, like `trace.log.1`.
+		
+		let mut path = self.path.clone().into_os_string();
+		path.push(format!(".{index}"));
+		PathBuf::from(path)
+	}
+	
+	fn rotate(&self) -> std::io::Result<(std::io::BufWriter<std::fs::File>, u64)> {
+		//! Shifts each rotated file up by one, dropping the oldest, and moves
+		//! the current file to `.1`. Returns the new, empty file.
+		
+		if self.max_files == 0 {
+			std::fs::File::create(&self.path)?;
+			return Self::open(&self.path)
+		}
+		for index in (1..self.max_files).rev() {
+			let from = self.rotated_path(index);
+			if from.exists() {
+				std::fs::rename(from, self.rotated_path(index + 1))?;
+			}
+		}
+		std::fs::rename(&self.path, self.rotated_path(1))?;
+		Self::open(&self.path)
+	}
+	
+	fn write(&self, line: &str) -> std::io::Result<()> {
+		use std::io::Write;
+		
+		let mut file = lock(&self.file);
+		let line_len = line.len() as u64 + 1;
+		if file.1 > 0 && file.1 + line_len > self.max_bytes {
+			file.0.flush()?;
+			*file = self.rotate()?;
+		}
+		
+		writeln!(file.0, "{line}")?;
+		file.1 += line_len;
+		Ok(())
+	}
+}
+
+impl TraceSink for FileSink {
+	fn write_line(&self, line: &str) {
+		if let Err(e) = self.write(line) {
+			lock(&self.error).get_or_insert(e);
+		}
+	}
+	
+	fn flush(&self) -> std::io::Result<()> {
+		use std::io::Write;
+		
+		let result = lock(&self.file).0.flush();
+		match lock(&self.error).take() {
+			Some(e) => Err(e),
+			None => result,
+		}
+	}
+}
+
+/// Installs a sink (see [`set_sink`]) that appends each line to the file at
+/// `path`, creating it if needed.
+/// 
+/// Before a line would grow the file past `max_bytes`, the file is rotated:
+/// it's renamed with a `.1` suffix, any earlier rotated files are shifted up to
+/// `.2`, `.3`, and so on, and a new file is started. At most `max_files`
+/// rotated files are kept, so the oldest is dropped. A `max_files` of 0 clears
+/// the file instead. A line longer than `max_bytes` is still written whole.
+/// 
+/// Lines are buffered, and written out before rotating, by [`flush`], and when
+/// the sink is replaced or cleared. The first error while writing is kept and
+/// returned by the next [`flush`], which writes out the other lines anyway.
+/// 
+/// # Errors
+/// 
+/// Returns any error from opening the file.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// let dir = std::env::temp_dir().join(format!("trace-file-output-{}", std::process::id()));
+/// std::fs::create_dir_all(&dir).unwrap();
+/// let path = dir.join("trace.log");
+/// 
+/// trace::set_file_output(&path, 32, 3).unwrap();
+/// for i in 0..6 {
+///     trace!("line {i}");
+/// }
+/// trace::flush().unwrap();
+/// trace::clear_sink();
+/// 
+/// # if cfg!(feature = "trace") {
+/// let mut lines = Vec::new();
+/// for name in ["trace.log.2", "trace.log.1", "trace.log"] {
+///     let text = std::fs::read_to_string(dir.join(name)).unwrap();
+///     assert!(text.len() <= 32);
+///     lines.extend(text.lines().map(|line| line.split("|   ").last().unwrap().to_owned()));
+/// }
+/// assert_eq!(lines, ["line 0", "line 1", "line 2", "line 3", "line 4", "line 5"]);
+/// # }
+/// std::fs::remove_dir_all(dir).unwrap();
+/// ```
+pub fn set_file_output(
+	path: impl AsRef<Path>,
+	max_bytes: u64,
+	max_files: usize,
+) -> std::io::Result<()> {
+	let path = path.as_ref().to_owned();
+	let file = FileSink::open(&path)?;
+	set_sink(Box::new(FileSink {
+		path,
+		max_bytes,
+		max_files,
+		file: Mutex::new(file),
+		error: Mutex::new(None),
+	}));
+	Ok(())
+}
+
+#[cfg(feature = "syslog")]
+pub use syslog::{Facility, Severity};
+
+/// A [`TraceSink`] forwarding lines to syslog, or printing them to the standard
+/// error if it's unavailable. See [`set_syslog`].
+#[cfg(feature = "syslog")]
+struct SyslogSink {
+	logger: Mutex<Option<syslog::Logger<syslog::LoggerBackend, syslog::Formatter3164>>>,
+	severity: Severity,
+}
+
+#[cfg(feature = "syslog")]
+impl SyslogSink {
+	fn install(
+		logger: syslog::Result<syslog::Logger<syslog::LoggerBackend, syslog::Formatter3164>>,
+		severity: Severity,
+	) -> std::io::Result<()> {
+		//! Installs a sink for the connected logger, or one that only prints to
+		//! the standard error if it couldn't connect, returning the error.
+		
+		let (logger, result) = match logger {
+			Ok(logger) => (Some(logger), Ok(())),
+			Err(e) => (None, Err(std::io::Error::other(e.to_string()))),
+		};
+		set_sink(Box::new(SyslogSink {
+			logger: Mutex::new(logger),
+			severity,
+		}));
+		result
+	}
+}
+
+#[cfg(feature = "syslog")]
+impl TraceSink for SyslogSink {
+	fn write_line(&self, line: &str) {
+		let mut logger = lock(&self.logger);
+		let is_sent = logger.as_mut().is_some_and(|logger| {
+			syslog::LogFormat::format(&logger.formatter, &mut logger.backend, self.severity, line).is_ok()
+		});
+		if !is_sent {
+			eprintln!("{line}");
+		}
+	}
+}
+
+/// Installs a sink (see [`set_sink`]) that forwards each line to the local
+/// syslog daemon (or journald) with the given facility and severity. Requires
+/// the `syslog` feature.
+/// 
+/// Lines are sent in the RFC 3164 format, with their indentation at the start
+/// of the message. If the connection fails, the sink is still installed but
+/// prints lines to the standard error instead, and the error is returned. Lines
+/// that fail to send later are printed to the standard error too.
+/// 
+/// # Errors
+/// 
+/// Returns an error if no syslog socket could be connected to.
+#[cfg(feature = "syslog")]
+pub fn set_syslog(facility: Facility, severity: Severity) -> std::io::Result<()> {
+	let formatter = syslog::Formatter3164 { facility, ..Default::default() };
+	SyslogSink::install(syslog::unix(formatter), severity)
+}
+
+/// Installs a sink like [`set_syslog`], connected to the syslog socket at the
+/// given path rather than a default one. Requires the `syslog` feature.
+/// 
+/// # Errors
+/// 
+/// Returns an error if the socket couldn't be connected to.
+/// 
+/// # Examples
+/// 
+/// ```
+/// # #[cfg(unix)] {
+/// use trace::{trace, Facility, Severity};
+/// use std::os::unix::net::UnixDatagram;
+/// use std::time::Duration;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// // A stand-in syslog daemon listening on its own socket:
+/// let path = std::env::temp_dir().join(format!("trace-syslog-{}.sock", std::process::id()));
+/// let daemon = UnixDatagram::bind(&path).unwrap();
+/// daemon.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+/// 
+/// trace::set_syslog_at(&path, Facility::LOG_USER, Severity::LOG_DEBUG).unwrap();
+/// trace!("started");
+/// 
+/// # if cfg!(feature = "trace") {
+/// let mut message = [0; 256];
+/// let len = daemon.recv(&mut message).unwrap();
+/// let message = std::str::from_utf8(&message[..len]).unwrap();
+/// assert!(message.starts_with("<15>"));
+/// assert!(message.ends_with("]: @---|   started"));
+/// # }
+/// 
+/// // Without a daemon, lines are printed to stderr instead:
+/// drop(daemon);
+/// std::fs::remove_file(&path).unwrap();
+/// assert!(trace::set_syslog_at(&path, Facility::LOG_USER, Severity::LOG_DEBUG).is_err());
+/// trace!("printed to stderr");
+/// trace::clear_sink();
+/// # }
+/// ```
+#[cfg(feature = "syslog")]
+pub fn set_syslog_at(
+	path: impl AsRef<Path>,
+	facility: Facility,
+	severity: Severity,
+) -> std::io::Result<()> {
+	let formatter = syslog::Formatter3164 { facility, ..Default::default() };
+	SyslogSink::install(syslog::unix_custom(formatter, path), severity)
+}
+
+static DEFAULT_WRITER: Mutex<Option<Box<dyn std::io::Write + Send>>> = Mutex::new(None);
+
+/// Routes the lines printed by [`trace!`], [`try_trace!`], and [`trace_scope!`]
+/// to the given writer instead of the standard output, until
+/// [`clear_default_writer`].
+/// 
+/// Unlike a [`TraceSink`], the writer receives the raw bytes of each line,
+/// including its newline, and write errors are returned by [`try_trace!`]. The
+/// writer is flushed after every line, so no lines are lost if the program
+/// exits without dropping it (wrapping it in a [`BufWriter`] doesn't help), and
+/// by [`flush`]. An installed sink (see [`set_sink`]) takes priority.
+/// 
+/// [`trace!`]: crate::trace
+/// [`try_trace!`]: crate::try_trace
+/// [`trace_scope!`]: crate::trace_scope
+/// [`BufWriter`]: std::io::BufWriter
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace;
+/// use std::io;
+/// use std::sync::{Arc, Mutex};
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// #[derive(Clone, Default)]
+/// struct Shared(Arc<Mutex<Vec<u8>>>);
+/// 
+/// impl io::Write for Shared {
+///     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+///         self.0.lock().unwrap().write(buf)
+///     }
+///     fn flush(&mut self) -> io::Result<()> {
+///         Ok(())
+///     }
+/// }
+/// 
+/// fn greet() {
+///     trace!("Hello");
+/// }
+/// 
+/// let out = Shared::default();
+/// trace::set_default_writer(Box::new(out.clone()));
+/// trace!("start");
+/// greet();
+/// trace::clear_default_writer();
+/// 
+/// # if cfg!(feature = "trace") {
+/// let out = String::from_utf8(out.0.lock().unwrap().clone()).unwrap();
+/// assert_eq!(out, "@---|   start\n    >---|   Hello\n");
+/// # }
+/// ```
+pub fn set_default_writer(writer: Box<dyn std::io::Write + Send>) {
+	*lock(&DEFAULT_WRITER) = Some(writer);
+}
+
+/// Removes the writer installed by [`set_default_writer`], flushing it and
+/// printing to the standard output again.
+pub fn clear_default_writer() {
+	if let Some(mut writer) = lock(&DEFAULT_WRITER).take() {
+		writer.flush().ok();
+	}
+}
+
+/// The structure of a trace, passed to the formatter set by [`set_formatter`].
+/// 
+/// The depths count frames from the bottom of the call stack, so the
+/// indentation level of a trace is `depth - basis_depth` (see [`set_json`]).
+/// They're `None` if the call stack couldn't be captured.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TraceRecord<'a> {
+	/// The depth of the traced function.
+	pub depth: Option<usize>,
+	
+	/// The number of frames matching the previous trace's call stack.
+	pub match_depth: Option<usize>,
+	
+	/// The depth of the baseline frame.
+	pub basis_depth: Option<usize>,
+	
+	/// The module that the trace was made in.
+	pub module_path: &'a str,
+	
+	/// The name of the current thread, if it has one.
+	pub thread: Option<&'a str>,
+	
+	/// The formatted message, including any function name or location added
+	/// by [`set_show_function`] or [`set_show_location`].
+	pub message: &'a str,
+}
+
+type Formatter = dyn Fn(&TraceRecord) -> String + Send + Sync;
+
+static FORMATTER: RwLock<Option<Arc<Formatter>>> = RwLock::new(None);
+
+/// Formats each trace line with the given function, in place of the built-in
+/// indentation, until [`clear_formatter`].
+/// 
+/// The returned string is printed as is, without any prefix (like timestamps),
+/// followed by a newline. This takes precedence over [`set_json`].
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::{trace_to, TraceRecord};
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// fn count_down(out: &mut Vec<u8>, n: u8) {
+///     trace_to!(out, "n:{n}").unwrap();
+///     if n > 0 {
+///         count_down(out, n - 1);
+///     }
+/// }
+/// 
+/// trace::set_formatter(Box::new(|record: &TraceRecord| {
+///     let level = record.depth.unwrap_or(0) - record.basis_depth.unwrap_or(0);
+///     format!("{}- {}", "  ".repeat(level), record.message)
+/// }));
+/// let mut out = Vec::new();
+/// count_down(&mut out, 2);
+/// 
+/// # if cfg!(feature = "trace") {
+/// assert_eq!(
+///     String::from_utf8(out).unwrap(),
+///     "    - n:2\n      - n:1\n        - n:0\n",
+/// );
+/// # }
+/// ```
+pub fn set_formatter(formatter: Box<Formatter>) {
+	*write_lock(&FORMATTER) = Some(Arc::from(formatter));
+}
+
+/// Removes the formatter installed by [`set_formatter`], indenting traces as
+/// usual again.
+pub fn clear_formatter() {
+	*write_lock(&FORMATTER) = None;
+}
+
+/// A [`TraceSink`] that collects traces into a tree, instead of printing them.
+/// 
+/// Clones share the same traces, so one clone can be installed with
+/// [`set_sink`] while another is kept to call [`into_tree`] later. Each trace
+/// becomes a child of the closest earlier trace with a lower indentation level,
+/// or of the root.
+/// 
+/// [`into_tree`]: TraceRecorder::into_tree
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::{trace, TraceNode, TraceRecorder};
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// fn s(n: u8, k: u8) -> u8 {
+///     trace!("n:{n}, k:{k}");
+///     if n == k {
+///         return 1
+///     }
+///     if k == 0 || n < k {
+///         return 0
+///     }
+///     s(n-1, k-1) + s(n-1, k)*k
+/// }
+/// 
+/// let recorder = TraceRecorder::new();
+/// trace::set_sink(Box::new(recorder.clone()));
+/// trace!("# of ways to group 3 items into 2 unordered sets:");
+/// trace!("Result: {}", s(3, 2));
+/// trace::clear_sink();
+/// 
+/// fn messages(node: &TraceNode) -> Vec<&str> {
+///     node.children.iter().map(|child| child.message.as_str()).collect()
+/// }
+/// 
+/// # if cfg!(feature = "trace") {
+/// let root = recorder.into_tree();
+/// assert_eq!(messages(&root), [
+///     "# of ways to group 3 items into 2 unordered sets:",
+///     "Result: 3",
+/// ]);
+/// let s_3_2 = &root.children[0].children[0];
+/// assert_eq!(s_3_2.message, "n:3, k:2");
+/// assert_eq!(messages(s_3_2), ["n:2, k:1", "n:2, k:2"]);
+/// assert_eq!(messages(&s_3_2.children[0]), ["n:1, k:0", "n:1, k:1"]);
+/// assert_eq!(s_3_2.children[0].children[1].depth, s_3_2.depth + 2);
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct TraceRecorder {
+	/// The message and indentation level of each trace, in order.
+	traces: Arc<Mutex<Vec<(String, usize)>>>,
+}
+
+impl TraceRecorder {
+	/// Returns a recorder without any traces.
+	pub fn new() -> Self {
+		Self::default()
+	}
+	
+	/// Returns the traces recorded so far as the children of a root node, which
+	/// has an empty message and a depth of 0. This takes the traces of every
+	/// clone of the recorder.
+	pub fn into_tree(self) -> TraceNode {
+		let traces = std::mem::take(&mut *lock(&self.traces));
+		let mut root = TraceNode {
+			message: String::new(),
+			depth: 0,
+			children: Vec::new(),
+		};
+		for (message, depth) in traces {
+			let mut parent = &mut root;
+			while parent.children.last().is_some_and(|last| last.depth < depth) {
+				parent = parent.children.last_mut().unwrap();
+			}
+			parent.children.push(TraceNode {
+				message,
+				depth,
+				children: Vec::new(),
+			});
+		}
+		root
+	}
+}
+
+impl TraceSink for TraceRecorder {
+	fn write_line(&self, line: &str) {
+		lock(&self.traces).push((line.to_owned(), 0));
+	}
+	
+	fn write_record(&self, record: &TraceRecord, _line: &str) {
+		let depth = match (record.depth, record.basis_depth) {
+			(Some(depth), Some(basis_depth)) => depth.saturating_sub(basis_depth),
+			_ => 0,
+		};
+		lock(&self.traces).push((record.message.to_owned(), depth));
+	}
+}
+
+/// A trace collected by a [`TraceRecorder`], and the traces nested under it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceNode {
+	/// The trace's message, without indentation.
+	pub message: String,
+	
+	/// The trace's indentation level.
+	pub depth: usize,
+	
+	/// The traces nested under this one, in order.
+	pub children: Vec<TraceNode>,
+}
+
+/// A trace's place in a tree of traces linked by hand rather than by the call
+/// stack, for tasks that don't share one. See [`trace_ctx!`].
+/// 
+/// Each context has a depth, where a root is 0, and an identifier that's
+/// unique within the process, which can be included in messages to tell apart
+/// interleaved tasks.
+/// 
+/// [`trace_ctx!`]: crate::trace_ctx
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TraceCtx {
+	depth: usize,
+	id: u64,
+}
+
+static NEXT_CTX_ID: AtomicU64 = AtomicU64::new(0);
+
+impl TraceCtx {
+	/// Returns a new root context.
+	pub fn new() -> Self {
+		Self {
+			depth: 0,
+			id: NEXT_CTX_ID.fetch_add(1, Ordering::Relaxed),
+		}
+	}
+	
+	/// Returns a new context one level under this one.
+	pub fn child(self) -> Self {
+		Self {
+			depth: self.depth.saturating_add(1),
+			id: NEXT_CTX_ID.fetch_add(1, Ordering::Relaxed),
+		}
+	}
+	
+	/// Returns this context's depth, where a root is 0.
+	pub fn depth(self) -> usize {
+		self.depth
+	}
+	
+	/// Returns this context's identifier.
+	pub fn id(self) -> u64 {
+		self.id
+	}
+}
+
+impl Default for TraceCtx {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+/// An error returned by [`try_trace!`] when a trace can't be printed as usual.
+/// 
+/// An error from the writer — the standard stream, a [`set_default_writer`]
+/// writer, or the buffer flushed by [`set_buffered`] — is [`TraceError::Io`].
+/// [`trace!`] and the other panicking macros panic with its message instead.
+/// 
+/// A call stack that was captured but had no frames, like on a platform the
+/// `backtrace` crate can't unwind or from a [`set_frame_provider`] provider
+/// that returns none, isn't an error, since the line is still printed without
+/// indentation. See [`last_capture_failed`] to check for it.
+/// 
+/// Converts to and from [`std::io::Error`], so `?` keeps working in functions
+/// that return an [`std::io::Result`].
+/// 
+/// [`try_trace!`]: crate::try_trace
+/// [`trace!`]: crate::trace
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::{try_trace, TraceError};
+/// use std::io;
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+/// 
+/// struct BrokenPipe;
+/// 
+/// impl io::Write for BrokenPipe {
+///     fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+///         Err(io::ErrorKind::BrokenPipe.into())
+///     }
+///     fn flush(&mut self) -> io::Result<()> {
+///         Ok(())
+///     }
+/// }
+/// 
+/// trace::set_default_writer(Box::new(BrokenPipe));
+/// let result = try_trace!("Hello");
+/// let panic = std::panic::catch_unwind(|| trace::trace!("Hello"));
+/// trace::clear_default_writer();
+/// 
+/// # if cfg!(feature = "indent") {
+/// match result {
+///     Err(TraceError::Io(e)) => assert_eq!(e.kind(), io::ErrorKind::BrokenPipe),
+///     _ => panic!("expected an IO error"),
+/// }
+/// let message = panic.unwrap_err().downcast::<String>().unwrap();
+/// assert!(message.starts_with("failed printing to the default writer: "));
+/// 
+/// let result = try_trace!("Hello");
+/// assert!(result.is_ok());
+/// # }
+/// ```
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum TraceError {
+	/// Writing the line failed.
+	Io(std::io::Error),
+}
+
+impl std::fmt::Display for TraceError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::Io(e) => e.fmt(f),
+		}
+	}
+}
+
+impl std::error::Error for TraceError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			Self::Io(e) => Some(e),
+		}
+	}
+}
+
+impl From<std::io::Error> for TraceError {
+	fn from(e: std::io::Error) -> Self {
+		Self::Io(e)
+	}
+}
+
+impl From<TraceError> for std::io::Error {
+	fn from(e: TraceError) -> Self {
+		match e {
+			TraceError::Io(e) => e,
+		}
+	}
+}
+
+/// The standard stream that traces are printed to. See [`set_output_stream`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputStream {
+	/// The standard output.
+	#[default]
+	Stdout,
+	
+	/// The standard error.
+	Stderr,
+}
+
+#[cfg(feature = "indent")]
+impl OutputStream {
+	const fn name(self) -> &'static str {
+		match self {
+			Self::Stdout => "stdout",
+			Self::Stderr => "stderr",
+		}
+	}
+}
+
+static OUTPUT_STREAM: RwLock<OutputStream> = RwLock::new(OutputStream::Stdout);
+
+/// Sets the stream that [`trace!`], [`try_trace!`], and [`trace_scope!`] print
+/// to. Defaults to [`OutputStream::Stdout`].
+/// 
+/// This is overridden by [`set_sink`], and other macros like [`trace_err!`]
+/// keep printing to their own streams. Only the standard output is buffered by
+/// [`set_buffered`].
+/// 
+/// [`trace!`]: crate::trace
+/// [`try_trace!`]: crate::try_trace
+/// [`trace_scope!`]: crate::trace_scope
+/// [`trace_err!`]: crate::trace_err
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::{trace, OutputStream};
+/// 
+/// trace::set_output_stream(OutputStream::Stderr);
+/// trace!("printed to stderr");
+/// assert_eq!(trace::output_stream(), OutputStream::Stderr);
+/// ```
+pub fn set_output_stream(stream: OutputStream) {
+	*write_lock(&OUTPUT_STREAM) = stream;
+}
+
+/// Returns the stream traces are printed to. See [`set_output_stream`].
+pub fn output_stream() -> OutputStream {
+	*read_lock(&OUTPUT_STREAM)
+}
+
+static BUFFERED: AtomicBool = AtomicBool::new(false);
+
+/// Enables or disables buffering the lines printed to the standard output by
+/// [`trace!`], [`try_trace!`], and [`trace_scope!`]. Disabled by default.
+/// 
+/// Each thread collects its lines in its own buffer, which is written out when
+/// it fills up, when [`flush`] is called, or when the thread exits or unwinds
+/// from a panic. This is much faster for many traces in a row, but lines may
+/// appear late and write errors are reported by [`flush`] instead. Traces
+/// printed to a [`TraceSink`] aren't buffered.
+/// 
+/// The main thread's buffer isn't flushed when the program exits, so call
+/// [`flush`] before returning from `main`, or hold a [`FlushGuard`] there so
+/// its lines are also written out if `main` panics.
+/// 
+/// [`trace!`]: crate::trace
+/// [`try_trace!`]: crate::try_trace
+/// [`trace_scope!`]: crate::trace_scope
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace;
+/// use std::process::Command;
+/// 
+/// fn outer() {
+///     trace!("outer");
+///     inner();
+/// }
+/// 
+/// fn inner() {
+///     trace!("inner");
+/// }
+/// 
+/// if let Ok(mode) = std::env::var("TRACE_BUFFERED_CHILD") {
+///     let _guard = trace::FlushGuard::new();
+///     trace::set_buffered(mode != "unbuffered");
+///     outer();
+///     outer();
+///     if mode == "panic" {
+///         panic!("buffered lines are still written out");
+///     }
+///     return
+/// }
+/// 
+/// let run = |mode| {
+///     Command::new(std::env::current_exe().unwrap())
+///         .env("RUST_LIB_BACKTRACE", "1")
+///         .env("TRACE_BUFFERED_CHILD", mode)
+///         .output()
+///         .unwrap()
+/// };
+/// let buffered = run("buffered");
+/// let unbuffered = run("unbuffered");
+/// let panicked = run("panic");
+/// assert!(buffered.status.success() && !panicked.status.success());
+/// assert_eq!(buffered.stdout, unbuffered.stdout);
+/// assert_eq!(panicked.stdout, unbuffered.stdout);
+/// 
+/// # if cfg!(feature = "trace") {
+/// assert_eq!(String::from_utf8(buffered.stdout).unwrap(), "\
+/// @--->---|   outer
+///         >---|   inner
+///         |   outer
+///         >---|   inner
+/// ");
+/// # }
+/// ```
+pub fn set_buffered(enabled: bool) {
+	BUFFERED.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns whether output is buffered. See [`set_buffered`].
+pub fn buffered() -> bool {
+	BUFFERED.load(Ordering::Relaxed)
+}
+
+/// A guard that calls [`flush`] when dropped, even while unwinding from a
+/// panic, ignoring any error. See [`set_buffered`].
+/// 
+/// Holding one in `main` keeps the main thread's buffered lines from being lost
+/// when it returns or panics, since its buffer isn't dropped then.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace;
+/// 
+/// fn main() {
+///     let _guard = trace::FlushGuard::new();
+///     trace::set_buffered(true);
+///     trace!("printed as `main` returns");
+/// }
+/// ```
+#[must_use = "lines are flushed as soon as this is dropped"]
+#[derive(Debug, Default)]
+pub struct FlushGuard(());
+
+impl FlushGuard {
+	/// Returns a guard that flushes when dropped.
+	pub const fn new() -> Self {
+		Self(())
+	}
+}
+
+impl Drop for FlushGuard {
+	fn drop(&mut self) {
+		flush().ok();
+	}
+}
+
+/// Writes out the current thread's buffered lines. See [`set_buffered`].
+/// 
+/// With the `background` feature, this also waits for the lines queued by
+/// every thread to be printed. See `set_background`. The writer installed by
+/// [`set_default_writer`] or sink installed by [`set_sink`], if any, is flushed
+/// too, and the current thread's run of repeated lines is summarized (see
+/// [`set_collapse_repeats`]).
+/// 
+/// # Errors
+/// 
+/// Returns any error from writing to the standard output or flushing the
+/// default writer or sink, including those of lines buffered earlier.
+pub fn flush() -> std::io::Result<()> {
+	#[cfg(feature = "indent")]
+	end_repeats()?;
+	#[cfg(feature = "background")]
+	if let Some(sender) = BACKGROUND_SENDER.get() {
+		let (done, wait) = std::sync::mpsc::sync_channel(0);
+		if lock(sender).send(Emission::Flush(done)).is_ok() {
+			wait.recv().ok();
+		}
+	}
+	if let Some(writer) = &mut *lock(&DEFAULT_WRITER) {
+		writer.flush()?;
+	}
+	if let Some(sink) = &*lock(&SINK) {
+		sink.flush()?;
+	}
+	#[cfg(feature = "indent")]
+	flush_buffer()?;
+	Ok(())
+}
+
+#[cfg(feature = "indent")]
+fn end_repeats() -> io::Result<()> {
+	//! Prints the summary of the current thread's run of repeated lines traced
+	//! to the output of [`trace!`], if any. See [`set_collapse_repeats`].
+	
+	let repeated = STATE.with(|state| state.try_borrow_mut().ok()?.repeated_line.take());
+	match repeated {
+		Some(repeated) if repeated.count > 1 => write_output(|writer, _| repeated.write_summary(writer)),
+		_ => Ok(()),
+	}
+}
+
+#[cfg(feature = "indent")]
+fn flush_buffer() -> io::Result<()> {
+	//! Writes out the current thread's buffered lines, if any. See
+	//! [`set_buffered`].
+	
+	match BUFFER.with_borrow_mut(Option::take) {
+		Some(buffer) => buffer.into_inner().map(drop).map_err(io::IntoInnerError::into_error),
+		None => Ok(()),
+	}
+}
+
+#[cfg(feature = "background")]
+static BACKGROUND: AtomicBool = AtomicBool::new(false);
+
+/// Enables or disables printing from a background thread. Disabled by default.
+/// Requires the `background` feature.
+/// 
+/// While enabled, the lines of [`trace!`], [`try_trace!`], and [`trace_scope!`]
+/// are queued for a dedicated thread to write to the default writer (see
+/// [`set_default_writer`]) or output stream, so the calling thread doesn't wait
+/// on I/O (this is useful in async runtimes). Lines queued by one thread are
+/// printed in order, and write errors are ignored.
+/// 
+/// Only the writing is moved off the calling thread. Its call stack is still
+/// captured, resolved, and formatted before the macro returns, since the line's
+/// indentation depends on the traces before it on the same thread. An installed
+/// sink (see [`set_sink`]) isn't affected.
+/// 
+/// Queued lines aren't printed when the program exits, so call [`flush`] before
+/// returning from `main`.
+/// 
+/// [`trace!`]: crate::trace
+/// [`try_trace!`]: crate::try_trace
+/// [`trace_scope!`]: crate::trace_scope
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace;
+/// use std::io;
+/// use std::sync::{Arc, Mutex};
+/// 
+/// #[derive(Clone, Default)]
+/// struct Shared(Arc<Mutex<Vec<u8>>>);
+/// 
+/// impl io::Write for Shared {
+///     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+///         self.0.lock().unwrap().write(buf)
+///     }
+///     fn flush(&mut self) -> io::Result<()> {
+///         Ok(())
+///     }
+/// }
+/// 
+/// let out = Shared::default();
+/// trace::set_default_writer(Box::new(out.clone()));
+/// trace::set_background(true);
+/// for i in 0..100 {
+///     trace!("i:{i}");
+/// }
+/// trace::flush().unwrap();
+/// 
+/// let out = String::from_utf8(out.0.lock().unwrap().clone()).unwrap();
+/// let messages: Vec<&str> = out.lines()
+///     .map(|line| line.trim_start_matches([' ', '@', '>', '-', '|']))
+///     .collect();
+/// let expected: Vec<String> = (0..100).map(|i| format!("i:{i}")).collect();
+/// assert_eq!(messages, expected);
+/// ```
+#[cfg(feature = "background")]
+pub fn set_background(enabled: bool) {
+	BACKGROUND.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns whether lines are printed from a background thread. See
+/// [`set_background`].
+#[cfg(feature = "background")]
+pub fn background() -> bool {
+	BACKGROUND.load(Ordering::Relaxed)
+}
+
+/// A message to the background printing thread. See [`set_background`].
+#[cfg(feature = "background")]
+enum Emission {
+	/// A finished line to print.
+	Line(OutputStream, Vec<u8>),
+	
+	/// A signal to send once every earlier line is printed.
+	Flush(std::sync::mpsc::SyncSender<()>),
+}
+
+#[cfg(feature = "background")]
+static BACKGROUND_SENDER: OnceLock<Mutex<std::sync::mpsc::Sender<Emission>>> = OnceLock::new();
+
+#[cfg(feature = "background")]
+fn send_to_background(emission: Emission) {
+	//! Queues a message for the background printing thread, starting it if
+	//! needed. See [`set_background`].
+	
+	use io::Write;
+	
+	let sender = BACKGROUND_SENDER.get_or_init(|| {
+		let (sender, receiver) = std::sync::mpsc::channel();
+		std::thread::Builder::new()
+			.name("trace".to_owned())
+			.spawn(move || for emission in receiver {
+				match emission {
+					Emission::Line(stream, line) => match (&mut *lock(&DEFAULT_WRITER), stream) {
+						(Some(writer), _) => {
+							writer.write_all(&line).and_then(|()| writer.flush()).ok();
+						},
+						(None, OutputStream::Stdout) => {
+							io::stdout().write_all(&line).ok();
+						},
+						(None, OutputStream::Stderr) => {
+							io::stderr().write_all(&line).ok();
+						},
+					},
+					Emission::Flush(done) => {
+						io::stdout().flush().ok();
+						done.send(()).ok();
+					},
+				}
+			})
+			.expect("failed to spawn the background trace thread");
+		Mutex::new(sender)
+	});
+	lock(sender).send(emission).ok();
+}
+
+/// Expands to the first block if the `indent` feature (implied by `trace`) is
+/// enabled.
+#[cfg(feature = "indent")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! _cfg_trace {
+	({$($enabled:tt)*} else {$($disabled:tt)*}) => {{
+		$($enabled)*
+	}};
+}
+
+/// Expands to the second block if the `indent` feature is disabled.
+#[cfg(not(feature = "indent"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! _cfg_trace {
+	({$($enabled:tt)*} else {$($disabled:tt)*}) => {{
+		$($disabled)*
+	}};
+}
+
+/// A guard that prints the exit line of a [`trace_scope!`] when dropped.
+/// 
+/// [`trace_scope!`]: crate::trace_scope
+#[cfg(feature = "indent")]
+#[must_use = "the scope's exit line is printed as soon as this is dropped"]
+pub struct TraceScope {
+	module_path: &'static str,
+	
+	/// The call stack captured at the start of the scope (see [`capture_stack`]),
+	/// or `None` if tracing was disabled then.
+	frames: Option<Option<Vec<Frame>>>,
+}
+
+#[cfg(feature = "indent")]
+impl Drop for TraceScope {
+	fn drop(&mut self) {
+		let Some(frames) = self.frames.take() else {
+			return
+		};
+		if !is_traced(self.module_path) {
+			return
+		}
+		let text = "}".to_owned();
+		let result = write_output(|writer, is_terminal| {
+			write_frames(writer, frames.as_deref(), text, self.module_path, is_terminal)
+		});
+		if let Err(e) = result {
+			panic_output(e);
+		}
+	}
+}
+
+/// A frame of a call stack, identified by its function and source location.
+#[cfg(feature = "indent")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Frame {
+	/// The demangled name of the function, like `crate::module::function`.
+	name: String,
+	
+	/// The source file and line number of the frame, if known.
+	location: Option<(PathBuf, u32)>,
+	
+	/// The raw symbol name of the function, if kept for
+	/// [`SymbolStyle::Mangled`].
+	mangled_name: Option<String>,
+}
+
+#[cfg(feature = "indent")]
+impl Frame {
+	fn is_in_crate(&self, crate_name: &str) -> bool {
+		//! Returns whether this frame's function is in the given crate.
+		
+		self.name.trim_start_matches('<')
+			.strip_prefix(crate_name)
+			.is_some_and(|path| path.starts_with("::"))
+	}
+	
+	fn display_name(&self, style: SymbolStyle) -> String {
+		//! Returns the name of this frame's function as shown by
+		//! [`set_show_function`].
+		
+		match style {
+			SymbolStyle::Short   => self.function_name(),
+			SymbolStyle::Full    => self.name.clone(),
+			SymbolStyle::Mangled => self.mangled_name.clone().unwrap_or_else(|| self.name.clone()),
+		}
+	}
+	
+	fn function_name(&self) -> String {
+		//! Returns the short name of this frame's function, without its path or
+		//! generic arguments, like `parse` or `parse::{closure}`.
+		
+		 // Remove Generic Arguments & Qualified Paths:
+		let mut path = String::with_capacity(self.name.len());
+		let mut nesting = 0_usize;
+		for c in self.name.chars() {
+			match c {
+				'<' => nesting += 1,
+				'>' => nesting = nesting.saturating_sub(1),
+				_ if nesting == 0 => path.push(c),
+				_ => {}
+			}
+		}
+		
+		 // Keep Last Function Segment:
+		let mut segments = path.rsplit("::").filter(|segment| !segment.is_empty());
+		let mut is_closure = false;
+		let name = segments.find(|segment| {
+			let is_closure_segment = segment.starts_with('{');
+			is_closure |= is_closure_segment;
+			!is_closure_segment
+		}).unwrap_or(&self.name);
+		
+		if is_closure {
+			format!("{name}::{{closure}}")
+		} else {
+			name.to_owned()
+		}
+	}
+}
+
+/// A line printed while repeats are collapsed, and how many times in a row it
+/// was traced. See [`set_collapse_repeats`].
+#[cfg(feature = "indent")]
+struct RepeatedLine {
+	/// The text printed left of the line's indentation, like a timestamp.
+	prefix: String,
+	
+	/// The line, without its prefix.
+	line: String,
+	
+	/// The number of times the line was traced in a row.
+	count: usize,
+}
+
+#[cfg(feature = "indent")]
+impl RepeatedLine {
+	fn write_summary<W>(&self, writer: &mut W) -> io::Result<()>
+	where
+		W: io::Write + ?Sized
+	{
+		//! Prints the line again with the length of its run, if it repeated.
+		
+		if self.count <= 1 {
+			return Ok(())
+		}
+		write_line(writer, format_args!("{}{} (x{})", self.prefix, self.line, self.count))
+	}
+}
+
+#[cfg(feature = "indent")]
+struct TraceState {
+	/// The frames of the last trace's call stack, from the bottom up.
+	last_trace: Vec<Frame>,
+	
+	/// The frames of the distinct call stacks traced before the last, from the
+	/// most recent. See [`set_history`].
+	history: VecDeque<Vec<Frame>>,
+	
+	/// The depth of the last trace's baseline frame.
+	basis_depth: usize,
+	
+	/// When the last trace was printed, if deltas are shown.
+	last_time: Option<Instant>,
+	
+	/// The last line printed by [`write_output`], if repeats are collapsed.
+	repeated_line: Option<RepeatedLine>,
+	
+	/// The number of groups started by [`trace_group!`] and not yet ended.
+	group_depth: usize,
+	
+	/// The number of traces made since the last sampled one. See
+	/// [`set_sample_rate`].
+	sample_count: usize,
+	
+	/// The call stack of the last [`trace_anchor!`], up to its caller.
+	anchor: Option<Vec<Frame>>,
+	
+	/// Whether the next call stack traced sets the anchor for a session. See
+	/// [`capture_session`].
+	is_session_pending: bool,
+	
+	/// The most levels a line has been indented by since the last reset. See
+	/// [`reset_with_summary`].
+	phase_depth: usize,
+	
+	/// The number of lines printed since the last reset. See
+	/// [`reset_with_summary`].
+	phase_count: usize,
+}
+
+#[cfg(feature = "indent")]
+impl TraceState {
+	const fn new() -> Self {
+		Self {
+			last_trace:  Vec::new(),
+			history:     VecDeque::new(),
+			basis_depth: 0,
+			last_time:   None,
+			repeated_line: None,
+			group_depth: 0,
+			sample_count: 0,
+			anchor:      None,
+			is_session_pending: false,
+			phase_depth: 0,
+			phase_count: 0,
+		}
+	}
+	
+	fn anchor_session(&mut self, frames: &[Frame]) {
+		//! Anchors a session started by [`capture_session`] at its own frame in
+		//! the first call stack traced within it, so that the session's function
+		//! is indented as if it were called by a main function.
+		
+		if !std::mem::take(&mut self.is_session_pending) {
+			return
+		}
+		let crate_name = module_path!();
+		if let Some(session_depth) = frames.iter().rposition(|frame| frame.is_in_crate(crate_name)) {
+			self.anchor = Some(frames[..=session_depth].to_vec());
+		}
+	}
+}
+
+#[cfg(feature = "indent")]
+thread_local! {
+	static STATE: RefCell<TraceState> = const {
+		RefCell::new(TraceState::new())
+	};
+	
+	/// Lines waiting to be printed, flushed when dropped. See [`set_buffered`].
+	static BUFFER: RefCell<Option<io::BufWriter<io::Stdout>>> = const {
+		RefCell::new(None)
+	};
+	
+	/// Whether the last trace macro called on this thread printed a line. See
+	/// [`last_emitted`].
+	static LAST_EMITTED: Cell<bool> = const {
+		Cell::new(false)
+	};
+	
+	/// Whether a line is being written by [`write_output`] as is, so repeats of
+	/// it may be collapsed. See [`set_collapse_repeats`].
+	static IS_WRITING_OUTPUT: Cell<bool> = const {
+		Cell::new(false)
+	};
+	
+	/// Whether a line is being written for the installed [`TraceSink`].
+	static IS_WRITING_SINK: Cell<bool> = const {
+		Cell::new(false)
+	};
+	
+	/// The structure of the line being written for the installed [`TraceSink`].
+	static SINK_RECORD: RefCell<Option<SinkRecord>> = const {
+		RefCell::new(None)
+	};
+	
+	/// Whether the last call stack captured had no frames. See
+	/// [`last_capture_failed`].
+	static IS_CAPTURE_FAILED: Cell<bool> = const {
+		Cell::new(false)
+	};
+	
+	/// The destination the last line failed to be written to by [`write_output`].
+	static FAILED_OUTPUT: Cell<&'static str> = const {
+		Cell::new("stdout")
+	};
+}
+
+/// An owned [`TraceRecord`], kept until it's passed to the installed sink.
+#[cfg(feature = "indent")]
+struct SinkRecord {
+	depths: Option<[usize; 3]>,
+	module_path: String,
+	message: String,
+}
+
+#[cfg(feature = "indent")]
+#[doc(hidden)]
+#[track_caller]
+#[inline(never)]
+pub fn _trace(text: String, module_path: &str) {
+	//! Utility function for the [`trace!`] macro.
+	//! 
+	//! [`trace!`]: crate::trace
+	
+	if let Err(TraceError::Io(e)) = _try_trace(text, module_path) {
+		panic_output(e);
+	}
+}
+
+#[cfg(feature = "indent")]
+#[doc(hidden)]
+#[track_caller]
+#[inline(never)]
+pub fn _try_trace(text: String, module_path: &str) -> Result<(), TraceError> {
+	//! Utility function for the [`try_trace!`] macro.
+	//! 
+	//! [`try_trace!`]: crate::try_trace
+	
+	let location = Location::caller();
+	write_output(|writer, is_terminal| {
+		write_trace(writer, text, module_path, location, is_terminal)
+	}).map_err(TraceError::Io)
+}
+
+#[cfg(feature = "indent")]
+#[doc(hidden)]
+#[track_caller]
+#[inline(never)]
+pub fn _trace_peek(module_path: &str) {
+	//! Utility function for the [`peek_depth!`] macro.
+	//! 
+	//! [`peek_depth!`]: crate::peek_depth
+	
+	let location = Location::caller();
+	let result = write_output(|writer, is_terminal| {
+		let mut line = Vec::new();
+		let was_writing_output = IS_WRITING_OUTPUT.replace(false);
+		let result = write_trace(&mut line, String::new(), module_path, location, is_terminal);
+		IS_WRITING_OUTPUT.set(was_writing_output);
+		result?;
+		
+		 // Trim Fill of Trace's Own Symbol:
+		let line = String::from_utf8_lossy(&line);
+		match line.strip_suffix('\n') {
+			Some(line) => writeln!(writer, "{}", line.trim_end_matches(' ')),
+			None => Ok(()),
+		}
+	});
+	if let Err(e) = result {
+		panic_output(e);
+	}
+}
+
+#[cfg(feature = "indent")]
+#[doc(hidden)]
+#[track_caller]
+#[inline(never)]
+pub fn _trace_fmt(args: std::fmt::Arguments, module_path: &str) {
+	//! Utility function for the [`trace_fmt!`] macro.
+	//! 
+	//! [`trace_fmt!`]: crate::trace_fmt
+	
+	if _is_traced(module_path) {
+		_trace(args.to_string(), module_path);
+	}
+}
+
+#[cfg(feature = "indent")]
+#[doc(hidden)]
+#[track_caller]
+#[inline(never)]
+pub fn _trace_at(depth: usize, text: String, module_path: &str) {
+	//! Utility function for the [`trace_at!`] macro.
+	//! 
+	//! [`trace_at!`]: crate::trace_at
+	
+	let location = Location::caller();
+	let result = write_output(|writer, is_terminal| {
+		let text = located(text, location);
+		write_at(writer, depth, text, module_path, is_terminal)
+	});
+	if let Err(e) = result {
+		panic_output(e);
+	}
+}
+
+#[cfg(feature = "indent")]
+#[doc(hidden)]
+#[inline(never)]
+pub fn _trace_raw(block: &str, module_path: &str) {
+	//! Utility function for the [`indent_raw!`] macro.
+	//! 
+	//! [`indent_raw!`]: crate::indent_raw
+	
+	let result = write_output(|writer, is_terminal| {
+		let frames = capture_stack();
+		write_raw(writer, frames.as_deref(), block, module_path, is_terminal)
+	});
+	if let Err(e) = result {
+		panic_output(e);
+	}
+}
+
+#[cfg(feature = "indent")]
+#[doc(hidden)]
+#[track_caller]
+#[inline(never)]
+pub fn _trace_err(text: String, module_path: &str) {
+	//! Utility function for the [`trace_err!`] macro.
+	//! 
+	//! [`trace_err!`]: crate::trace_err
+	
+	let stderr = &mut io::stderr();
+	let is_terminal = cfg!(feature = "color") && stderr.is_terminal();
+	let location = Location::caller();
+	if let Err(e) = write_trace(stderr, text, module_path, location, is_terminal) {
+		panic!("failed printing to stderr: {e}");
+	}
+}
+
+#[cfg(feature = "indent")]
+#[doc(hidden)]
+#[track_caller]
+#[inline(never)]
+pub fn _trace_err_ctx(text: String, module_path: &str) {
+	//! Utility function for the [`trace_err_ctx!`] macro.
+	//! 
+	//! [`trace_err_ctx!`]: crate::trace_err_ctx
+	
+	let location = Location::caller();
+	let result = write_output(|writer, is_terminal| {
+		if !is_traced(module_path) {
+			return Ok(())
+		}
+		let text = located(text, location);
+		let mut frames = capture_stack();
+		
+		 // Skip Closure & Its Caller (e.g. `Result::map_err`):
+		if let Some(frames) = &mut frames {
+			if frames.last().is_some_and(|frame| frame.name.ends_with("{{closure}}")) {
+				frames.pop();
+				while frames.last().is_some_and(|frame| frame.is_in_crate("core")) {
+					frames.pop();
+				}
+			}
+		}
+		
+		write_frames(writer, frames.as_deref(), text, module_path, is_terminal)
+	});
+	if let Err(e) = result {
+		panic_output(e);
+	}
+}
+
+#[cfg(feature = "indent")]
+#[doc(hidden)]
+pub fn _hex_dump(bytes: &[u8], limit: usize) -> String {
+	//! Utility function for the [`trace_hex!`] macro.
+	//! 
+	//! [`trace_hex!`]: crate::trace_hex
+	
+	const ROW_LEN: usize = 16;
+	
+	let byte_unit = if bytes.len() == 1 { "byte" } else { "bytes" };
+	let mut text = format!("{} {byte_unit}", bytes.len());
+	let shown = &bytes[..bytes.len().min(limit)];
+	for (row_index, row) in shown.chunks(ROW_LEN).enumerate() {
+		 // Offset & Hex Values:
+		text += &format!("\n{:08x} ", row_index * ROW_LEN);
+		for index in 0..ROW_LEN {
+			if index == ROW_LEN / 2 {
+				text.push(' ');
+			}
+			match row.get(index) {
+				Some(byte) => text += &format!(" {byte:02x}"),
+				None => text += "   ",
+			}
+		}
+		
+		 // ASCII Characters:
+		let ascii: String = row.iter()
+			.map(|&byte| if byte.is_ascii_graphic() || byte == b' ' { byte as char } else { '.' })
+			.collect();
+		text += &format!("  |{ascii}|");
+	}
+	
+	let hidden_len = bytes.len() - shown.len();
+	if hidden_len > 0 {
+		let byte_unit = if hidden_len == 1 { "byte" } else { "bytes" };
+		text += &format!("\n… {hidden_len} more {byte_unit}");
+	}
+	text
+}
+
+#[cfg(feature = "indent")]
+#[doc(hidden)]
+pub fn _table(pairs: &[(String, String)]) -> String {
+	//! Utility function for the [`trace_table!`] macro.
+	//! 
+	//! [`trace_table!`]: crate::trace_table
+	
+	let key_width = pairs.iter()
+		.map(|(key, _)| key.chars().count())
+		.max()
+		.unwrap_or(0);
+	let value_indent = format!("\n{}", " ".repeat(key_width + 2));
+	
+	let rows: Vec<String> = pairs.iter()
+		.map(|(key, value)| {
+			let value = value.replace("\r\n", "\n").replace('\n', &value_indent);
+			format!("{key:>key_width$}: {value}")
+		})
+		.collect();
+	rows.join("\n")
+}
+
+#[cfg(feature = "log")]
+#[doc(hidden)]
+pub use log as _log;
+
+#[cfg(feature = "indent")]
+#[doc(hidden)]
+#[track_caller]
+#[inline(never)]
+pub fn _trace_string(text: String, module_path: &str) -> String {
+	//! Utility function for the [`trace_string!`] macro.
+	//! 
+	//! [`trace_string!`]: crate::trace_string
+	
+	let mut line = Vec::new();
+	write_trace(&mut line, text, module_path, Location::caller(), false)
+		.expect("writing to a Vec can't fail");
+	
+	if line.ends_with(b"\n") {
+		line.pop();
+	}
+	String::from_utf8(line).expect("traces are valid UTF-8")
+}
+
+#[cfg(all(feature = "indent", feature = "log"))]
+#[doc(hidden)]
+#[track_caller]
+#[inline(never)]
+pub fn _trace_log(level: log::Level, text: String, module_path: &str) {
+	//! Utility function for the [`trace_log!`] macro.
+	//! 
+	//! [`trace_log!`]: crate::trace_log
+	
+	let line = _trace_string(text, module_path);
+	if !line.is_empty() {
+		log::log!(target: module_path, level, "{line}");
+	}
+}
+
+#[cfg(feature = "indent")]
+#[doc(hidden)]
+#[track_caller]
+#[inline(never)]
+pub fn _trace_scope(label: Option<String>, module_path: &'static str) -> TraceScope {
+	//! Utility function for the [`trace_scope!`] macro.
+	//! 
+	//! [`trace_scope!`]: crate::trace_scope
+	
+	let Some(label) = label else {
+		return TraceScope { module_path, frames: None }
+	};
+	
+	let frames = capture_stack();
+	let text = located(format!("{label} {{"), Location::caller());
+	let result = write_output(|writer, is_terminal| {
+		write_frames(writer, frames.as_deref(), text, module_path, is_terminal)
+	});
+	if let Err(e) = result {
+		panic_output(e);
+	}
+	
+	TraceScope { module_path, frames: Some(frames) }
+}
+
+#[cfg(feature = "indent")]
+#[doc(hidden)]
+#[inline(never)]
+pub fn _trace_anchor() {
+	//! Utility function for the [`trace_anchor!`] macro.
+	
+	let frames = capture_stack();
+	STATE.with_borrow_mut(|state| {
+		state.anchor = frames;
+		state.is_session_pending = false;
+	});
+}
+
+#[cfg(feature = "indent")]
+#[doc(hidden)]
+pub fn _start_group() {
+	//! Utility function for the [`trace_group!`] macro.
+	
+	STATE.with_borrow_mut(|state| state.group_depth += 1);
+}
+
+#[cfg(feature = "indent")]
+#[doc(hidden)]
+pub fn _end_group() {
+	//! Utility function for the [`trace_group_end!`] macro.
+	
+	STATE.with_borrow_mut(|state| {
+		state.group_depth = state.group_depth.saturating_sub(1);
+	});
+}
+
+#[cfg(feature = "indent")]
+#[doc(hidden)]
+pub fn _is_due(last: &Mutex<Option<Instant>>, period: Duration) -> bool {
+	//! Utility function for the [`trace_every!`] macro. Returns whether `period`
+	//! has passed since `last`, updating it if so.
+	//! 
+	//! [`trace_every!`]: crate::trace_every
+	
+	let now = Instant::now();
+	let mut last = lock(last);
+	if last.is_some_and(|last| now.duration_since(last) < period) {
+		return false
+	}
+	*last = Some(now);
+	true
+}
+
+#[cfg(feature = "indent")]
+#[doc(hidden)]
+#[track_caller]
+#[inline(never)]
+pub fn _trace_to<W>(writer: &mut W, text: String, module_path: &str)
+	-> io::Result<()>
+where
+	W: io::Write + ?Sized
+{
+	//! Utility function for the [`trace_to!`] macro.
+	//! 
+	//! [`trace_to!`]: crate::trace_to
+	
+	write_trace(writer, text, module_path, Location::caller(), false)
+}
+
+/// Writes a trace line as if called with the given call stack, for testing how
+/// traces are indented. Requires the `test-support` feature.
+/// 
+/// `frames` are the function names of the call stack from the bottom up, like
+/// `["app::main", "app::run"]`, as seen by a trace from `module_path`. The line
+/// shares the indentation state of the current thread, but isn't filtered.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use trace::trace_frames;
+/// 
+/// let mut out = Vec::new();
+/// let mut trace = |frames: &[&str], text| {
+///     trace_frames(&mut out, frames, "app", text).unwrap();
+/// };
+/// trace(&["std::rt", "app::main"], "start");
+/// trace(&["std::rt", "app::main", "app::run"], "run");
+/// trace(&["std::rt", "app::main", "app::run", "app::step"], "step 1");
+/// trace(&["std::rt", "app::main", "app::run", "app::step"], "step 2");
+/// trace(&["std::rt", "app::main", "app::run"], "done");
+/// trace(&["std::rt", "app::main", "app::exit"], "exit");
+/// trace(&["std::thread", "app::work"], "thread");
+/// 
+/// assert_eq!(String::from_utf8(out).unwrap(), "\
+/// @   start
+/// >---|   run
+///     >---|   step 1
+///         |   step 2
+///     |   done
+///     |   exit
+/// @   thread
+/// ");
+/// ```
+/// 
+/// A trace from the root of its crate's call stack is marked `@   `, as is one
+/// with no frames in its crate:
+/// 
+/// ```
+/// use trace::trace_frames;
+/// 
+/// let mut out = Vec::new();
+/// let mut trace = |frames: &[&str], text| {
+///     trace_frames(&mut out, frames, "app", text).unwrap();
+/// };
+/// trace(&["app::main"], "root");
+/// trace(&["app::main"], "root again");
+/// trace(&["app::main", "app::run"], "run");
+/// trace(&["std::rt", "lib::callback"], "foreign");
+/// 
+/// assert_eq!(String::from_utf8(out).unwrap(), "\
+/// @   root
+/// @   root again
+/// @---|   run
+/// @   foreign
+/// ");
+/// ```
+/// 
+/// Diverging below the baseline moves it, and an empty call stack isn't
+/// indented:
+/// 
+/// ```
+/// use trace::trace_frames;
+/// 
+/// let mut out = Vec::new();
+/// let mut trace = |frames: &[&str], text| {
+///     trace_frames(&mut out, frames, "app", text).unwrap();
+/// };
+/// trace(&["std::rt", "std::init", "app::main", "app::run"], "run");
+/// trace(&["std::rt", "std::init", "app::main", "app::run"], "again");
+/// trace(&["std::rt", "std::hook", "app::on_exit"], "exit");
+/// trace(&[], "unknown");
+/// trace(&["std::rt", "std::hook", "app::on_exit"], "exit again");
+/// 
+/// assert_eq!(String::from_utf8(out).unwrap(), "\
+/// @---|   run
+///     |   again
+/// @   exit
+/// unknown
+/// |   exit again
+/// ");
+/// ```
+/// 
+/// Any sequence of call stacks is indented without panicking, however they
+/// relate to each other:
+/// 
+/// ```
+/// use trace::trace_frames;
+/// 
+/// let names = ["std::rt", "app::main", "app::run", "lib::parse", "<app::A as lib::B>::f", ""];
+/// let module_paths = ["app", "lib", "", "::"];
+/// let mut seed = 0x2545_F491_u32;
+/// let mut random = |n: usize| {
+///     seed ^= seed << 13;
+///     seed ^= seed >> 17;
+///     seed ^= seed << 5;
+///     seed as usize % n
+/// };
+/// 
+/// let mut out = Vec::new();
+/// for i in 0..2000 {
+///     if i == 1000 {
+///         trace::set_reanchor_on_crate_change(true);
+///         trace::set_max_depth(2);
+///     }
+///     let frames: Vec<&str> = (0..random(8)).map(|_| names[random(names.len())]).collect();
+///     let module_path = module_paths[random(module_paths.len())];
+///     trace_frames(&mut out, &frames, module_path, "x").unwrap();
+/// }
+/// 
+/// assert_eq!(String::from_utf8(out).unwrap().lines().count(), 2000);
+/// ```
+/// 
+/// An empty module path anchors the baseline to the bottom of the call stack:
+/// 
+/// ```
+/// use trace::trace_frames;
+/// 
+/// let mut out = Vec::new();
+/// let mut trace = |frames: &[&str], text| {
+///     trace_frames(&mut out, frames, "", text).unwrap();
+/// };
+/// trace(&["std::rt", "app::main"], "main");
+/// trace(&["std::rt", "app::main", "app::run"], "run");
+/// trace(&["std::rt", "app::main"], "back");
+/// 
+/// assert_eq!(String::from_utf8(out).unwrap(), "\
+/// @---|   main
+///     >---|   run
+///     |   back
+/// ");
+/// ```
+/// 
+/// Returning from deep calls only matches the frames still on the call stack,
+/// and diverging from the bottom moves the baseline back down:
+/// 
+/// ```
+/// use trace::trace_frames;
+/// 
+/// let mut out = Vec::new();
+/// let mut trace = |frames: &[&str], text| {
+///     trace_frames(&mut out, frames, "app", text).unwrap();
+/// };
+/// trace(&["std::rt", "app::main", "app::a", "app::b", "app::c", "app::d"], "deep");
+/// trace(&["std::rt", "app::main", "app::a"], "shallow");
+/// trace(&["std::rt", "app::main", "app::a", "app::b", "app::x"], "deep again");
+/// trace(&["std::rt", "app::main"], "shallower");
+/// trace(&["std::rt"], "below");
+/// trace(&["std::rt", "app::main", "app::a", "app::b"], "deep after");
+/// 
+/// assert_eq!(String::from_utf8(out).unwrap(), "\
+/// @--->--->--->---|   deep
+///     |   shallow
+///     >--->---|   deep again
+/// |   shallower
+/// @   below
+/// @--->---|   deep after
+/// ");
+/// ```
+#[cfg(feature = "test-support")]
+pub fn trace_frames<W>(writer: &mut W, frames: &[&str], module_path: &str, text: &str)
+	-> io::Result<()>
+where
+	W: io::Write + ?Sized
+{
+	let frames: Vec<Frame> = frames.iter()
+		.map(|name| Frame { name: (*name).to_owned(), location: None, mangled_name: None })
+		.collect();
+	write_frames(writer, Some(&frames), text.to_owned(), module_path, false)
+}
+
+#[cfg(feature = "indent")]
+fn write_trace<W>(
+	writer: &mut W,
+	text: String,
+	module_path: &str,
+	location: &Location,
+	is_terminal: bool,
+) -> io::Result<()>
+where
+	W: io::Write + ?Sized
+{
+	//! Writes an indented trace line. Colors are only used if `is_terminal`.
+	
+	if !is_traced(module_path) {
+		return Ok(())
+	}
+	
+	let text = located(text, location);
+	let frames = capture_stack();
+	write_frames(writer, frames.as_deref(), text, module_path, is_terminal)
+}
+
+#[cfg(feature = "indent")]
+fn located(text: String, location: &Location) -> String {
+	//! Appends the location of the traced macro call, if enabled. See
+	//! [`set_show_location`].
+	
+	if !show_location() {
+		return text
+	}
+	format!("{text} ({}:{})", location.file(), location.line())
+}
+
+#[cfg(feature = "indent")]
+fn write_output<F>(write: F) -> io::Result<()>
+where
+	F: FnOnce(&mut dyn io::Write, bool) -> io::Result<()>
+{
+	//! Writes a trace line to the installed [`TraceSink`] or default writer (see
+	//! [`set_default_writer`]), or the stream set by [`set_output_stream`]. The
+	//! second argument of `write` is whether colors may be used.
+	//! 
+	//! If writing fails, the destination is kept for [`panic_output`].
+	
+	let failed = |output: &'static str| move |_: &io::Error| FAILED_OUTPUT.set(output);
+	let write = |writer: &mut dyn io::Write, is_terminal| {
+		let was_writing_output = IS_WRITING_OUTPUT.replace(true);
+		let result = write(writer, is_terminal);
+		IS_WRITING_OUTPUT.set(was_writing_output);
+		result
+	};
+	
+	 // Format Before Locking, in Case Capturing or Formatting Traces:
+	if lock(&SINK).is_some() {
+		let mut line = Vec::new();
+		let was_writing_sink = IS_WRITING_SINK.replace(true);
+		let result = write(&mut line, false);
+		IS_WRITING_SINK.set(was_writing_sink);
+		let record = SINK_RECORD.take();
+		result.inspect_err(failed("the trace sink"))?;
+		let sink = lock(&SINK);
+		if let (Some(sink), Some(line)) = (&*sink, line.strip_suffix(b"\n")) {
+			let line = String::from_utf8_lossy(line);
+			let record = record.filter(|_| line != TRUNCATION_NOTICE);
+			match record {
+				Some(SinkRecord { depths, module_path, message }) => {
+					let thread = std::thread::current();
+					let record = TraceRecord {
+						depth:       depths.map(|depths| depths[0]),
+						match_depth: depths.map(|depths| depths[1]),
+						basis_depth: depths.map(|depths| depths[2]),
+						module_path: &module_path,
+						thread:      thread.name(),
+						message:     &message,
+					};
+					sink.write_record(&record, &line);
+				},
+				None => sink.write_line(&line),
+			}
+		}
+		return Ok(())
+	}
+	
+	 // Queue for the Background Thread:
+	#[cfg(feature = "background")]
+	if background() {
+		let stream = output_stream();
+		let is_terminal = cfg!(feature = "color") && lock(&DEFAULT_WRITER).is_none() && match stream {
+			OutputStream::Stdout => io::stdout().is_terminal(),
+			OutputStream::Stderr => io::stderr().is_terminal(),
+		};
+		let mut line = Vec::new();
+		write(&mut line, is_terminal).inspect_err(failed(stream.name()))?;
+		send_to_background(Emission::Line(stream, line));
+		return Ok(())
+	}
+	
+	 // Format Before Locking, in Case the Writer Traces:
+	if lock(&DEFAULT_WRITER).is_some() {
+		let mut line = Vec::new();
+		let result = write(&mut line, false).and_then(|()| {
+			if let Some(writer) = &mut *lock(&DEFAULT_WRITER) {
+				writer.write_all(&line)?;
+				writer.flush()?;
+			}
+			Ok(())
+		});
+		result.inspect_err(failed("the default writer"))?;
+		return Ok(())
+	}
+	
+	 // Hold the Stream's Lock for the Whole Line:
+	if output_stream() == OutputStream::Stderr {
+		let stderr = &mut io::stderr().lock();
+		let is_terminal = cfg!(feature = "color") && stderr.is_terminal();
+		return write(stderr, is_terminal).inspect_err(failed(OutputStream::Stderr.name()))
+	}
+	
+	let is_terminal = cfg!(feature = "color") && io::stdout().is_terminal();
+	 // Format Before Borrowing, so the Call Stack Isn't Captured Within It:
+	if buffered() {
+		let mut line = Vec::new();
+		write(&mut line, is_terminal).inspect_err(failed(OutputStream::Stdout.name()))?;
+		return BUFFER.with_borrow_mut(|buffer| {
+			use io::Write;
+			buffer.get_or_insert_with(|| io::BufWriter::new(io::stdout())).write_all(&line)
+		}).inspect_err(failed(OutputStream::Stdout.name()))
+	}
+	
+	 // Print Lines Buffered Before Buffering Was Disabled:
+	flush_buffer().inspect_err(failed(OutputStream::Stdout.name()))?;
+	write(&mut io::stdout().lock(), is_terminal).inspect_err(failed(OutputStream::Stdout.name()))
+}
+
+#[cfg(feature = "indent")]
+#[track_caller]
+fn panic_output(e: io::Error) -> ! {
+	//! Panics for a line that [`write_output`] failed to write, naming the
+	//! destination that failed.
+	
+	panic!("failed printing to {}: {e}", FAILED_OUTPUT.get());
+}
+
+#[cfg(feature = "indent")]
+#[doc(hidden)]
+pub fn _is_traced(module_path: &str) -> bool {
+	//! Utility function for the trace macros. Returns whether a trace from the
+	//! given module should be printed, so that its message is only formatted if
+	//! so. Each call counts towards the sample rate (see [`set_sample_rate`]).
+	
+	static ENV_SETTINGS: OnceLock<()> = OnceLock::new();
+	ENV_SETTINGS.get_or_init(apply_env_settings);
+	LAST_EMITTED.set(false);
+	IS_CAPTURE_FAILED.set(false);
+	
+	if !is_traced(module_path) {
+		return false
+	}
+	
+	 // Sample Every Nth Trace:
+	let sample_rate = sample_rate();
+	if sample_rate <= 1 {
+		return true
+	}
+	STATE.with(|state| {
+		let Ok(mut state) = state.try_borrow_mut() else {
+			return true
+		};
+		let is_sampled = state.sample_count == 0;
+		state.sample_count = (state.sample_count + 1) % sample_rate;
+		is_sampled
+	})
+}
+
+#[cfg(feature = "indent")]
+fn is_traced(module_path: &str) -> bool {
+	//! Returns whether a trace from the given module is enabled and allowed by
+	//! the module filters.
+	
+	if !is_enabled() {
+		return false
+	}
+	
+	let module_filter = read_lock(&MODULE_FILTER);
+	let is_allowed = module_filter.as_ref().is_none_or(|modules| {
+		modules.iter().any(|module| is_within_module(module_path, module))
+	});
+	
+	is_allowed && !read_lock(&MODULE_BLOCKLIST).iter()
+		.any(|module| is_within_module(module_path, module))
+}
+
+#[cfg(feature = "indent")]
+fn apply_env_settings() {
+	//! Applies the settings given by the `TRACE_FORMAT`, `TRACE_INDENT`, and
+	//! `TRACE_DISABLE` environment variables, ignoring invalid values and any
+	//! setting already made at runtime. See [`set_json`], [`set_indent_width`],
+	//! and [`set_enabled`].
+	
+	 // Disable Unless Set at Runtime:
+	let is_disabled = std::env::var("TRACE_DISABLE").is_ok_and(|value| {
+		["1", "true", "yes", "on"].iter().any(|truthy| value.trim().eq_ignore_ascii_case(truthy))
+	});
+	if is_disabled && !IS_ENABLED_SET.load(Ordering::Relaxed) {
+		ENABLED.store(false, Ordering::Relaxed);
+	}
+	
+	 // Set Format & Indentation Unless Set at Runtime:
+	if !IS_JSON_SET.load(Ordering::Relaxed) {
+		match std::env::var("TRACE_FORMAT").as_deref() {
+			Ok("json") => JSON.store(true, Ordering::Relaxed),
+			Ok("tree") => JSON.store(false, Ordering::Relaxed),
+			_ => {}
+		}
+	}
+	let indent_width = std::env::var("TRACE_INDENT").ok()
+		.and_then(|width| width.trim().parse::<usize>().ok())
+		.filter(|&width| width != 0);
+	if let Some(width) = indent_width {
+		if !IS_INDENT_WIDTH_SET.load(Ordering::Relaxed) {
+			set_config(TraceConfig {
+				indent_width: width,
+				..config()
+			});
+		}
+	}
+}
+
+fn is_within_module(module_path: &str, module: &str) -> bool {
+	//! Returns whether the module path is or is within the given module.
+	
+	module_path.strip_prefix(module)
+		.is_some_and(|path| path.is_empty() || path.starts_with("::"))
+}
+
+#[cfg(feature = "indent")]
+fn _trace_depth() -> Option<usize> {
+	//! Utility function for [`current_depth`].
+	
+	let frames = capture_stack()?;
+	
+	 // Caller's Crate:
+	let caller = frames.last()?.name.trim_start_matches('<');
+	let caller_crate = caller.split("::").next().unwrap().to_owned();
+	let baseline_crate = read_lock(&BASELINE_CRATE);
+	let crate_name = baseline_crate.as_deref().unwrap_or(&caller_crate);
+	
+	STATE.with(|state| {
+		let mut state = state.try_borrow_mut().ok()?;
+		state.anchor_session(&frames);
+		let TraceState { last_trace, history, basis_depth, group_depth, anchor, .. } = &mut *state;
+		compare_frames(&frames, last_trace, history, basis_depth, anchor.as_deref(), crate_name)
+			.map(|depths| depths.indent() + *group_depth)
+	})
+}
+
+#[cfg(feature = "indent")]
+fn capture_stack() -> Option<Vec<Frame>> {
+	//! Returns the frames of the local call stack from the bottom up, excluding
+	//! the frames from the first one within this crate, like the outermost
+	//! `_trace` function.
+	//! 
+	//! Returns `None` if the call stack shouldn't be captured. The frames given
+	//! by [`set_frame_provider`] are returned instead, if installed.
+	
+	 // Frames From Provider:
+	let frame_provider = read_lock(&FRAME_PROVIDER).clone();
+	if let Some(frame_provider) = frame_provider {
+		let frames: Vec<Frame> = frame_provider().into_iter()
+			.map(|name| Frame { name, location: None, mangled_name: None })
+			.collect();
+		if frames.is_empty() {
+			IS_CAPTURE_FAILED.set(true);
+		}
+		return Some(frames)
+	}
+	
+	#[cfg(feature = "trace")] {
+		capture_backtrace()
+	}
+	#[cfg(not(feature = "trace"))] {
+		None
+	}
+}
+
+#[cfg(feature = "trace")]
+fn capture_backtrace() -> Option<Vec<Frame>> {
+	//! Captures the frames for [`capture_stack`] with the `backtrace` crate.
+	
+	 // Only Capture When `std::backtrace::Backtrace::capture` Would:
+	static IS_CAPTURED: OnceLock<bool> = OnceLock::new();
+	let is_captured = *IS_CAPTURED.get_or_init(|| {
+		std::env::var_os("RUST_LIB_BACKTRACE")
+			.or_else(|| std::env::var_os("RUST_BACKTRACE"))
+			.is_some_and(|value| value != "0")
+	});
+	if !is_captured && !capture_forced() {
+		return None
+	}
+	
+	let is_mangled_kept = show_function() && symbol_style() == SymbolStyle::Mangled;
+	let mut frames = Vec::new();
+	backtrace::trace(|frame| {
+		let mut is_resolved = false;
+		backtrace::resolve_frame(frame, |symbol| {
+			is_resolved = true;
+			frames.push(Frame {
+				name: symbol.name()
+					.map_or_else(|| "<unknown>".to_owned(), |name| format!("{name:#}")),
+				location: symbol.filename()
+					.zip(symbol.lineno())
+					.map(|(file, line)| (file.to_owned(), line)),
+				mangled_name: symbol.name()
+					.filter(|_| is_mangled_kept)
+					.and_then(|name| name.as_str().map(str::to_owned)),
+			});
+		});
+		if !is_resolved {
+			 // Distinguish by Address (e.g. Stripped Binaries):
+			frames.push(Frame {
+				name: format!("<unknown {:p}>", frame.ip()),
+				location: None,
+				mangled_name: None,
+			});
+		}
+		true
+	});
+	frames.reverse();
+	
+	if frames.is_empty() {
+		static IS_WARNED: AtomicBool = AtomicBool::new(false);
+		if !IS_WARNED.swap(true, Ordering::Relaxed) {
+			eprintln!("trace: couldn't capture the call stack on this platform, so traces won't be indented");
+		}
+		IS_CAPTURE_FAILED.set(true);
+		return None
+	}
+	
+	 // Remove Frames Within This Crate (Entered Through a Non-Inlined Function):
+	let crate_name = module_path!();
+	if let Some(last_depth) = frames.iter()
+		.rposition(|frame| frame.is_in_crate(crate_name))
+	{
+		 // Keep Callers Run by This Crate (e.g. `capture_session`):
+		let trace_depth = frames[..last_depth].iter()
+			.rposition(|frame| !frame.is_in_crate(crate_name))
+			.map_or(0, |depth| depth + 1);
+		frames.truncate(trace_depth);
+	}
+	
+	Some(frames)
+}
+
+#[cfg(feature = "indent")]
+fn write_frames<W>(
+	writer: &mut W,
+	frames: Option<&[Frame]>,
+	text: String,
+	module_path: &str,
+	is_terminal: bool,
+) -> io::Result<()>
+where
+	W: io::Write + ?Sized
+{
+	//! Writes a trace line indented by the given call stack frames.
+	
+	let mut is_suppressed = false;
+	let mut depth = None;
+	let result = STATE.with(|state| {
+		 // Trace From Within a Trace (e.g. Inside a Writer):
+		let Ok(mut state) = state.try_borrow_mut() else {
+			return write_untraced(writer, text, module_path, &mut None)
+		};
+		if let Some(frames) = frames {
+			state.anchor_session(frames);
+		}
+		let TraceState { last_trace, history, basis_depth, last_time, anchor, .. } = &mut *state;
+		
+		let Some(frames) = frames else {
+			return write_untraced(writer, text, module_path, last_time)
+		};
+		
+		let baseline_crate = read_lock(&BASELINE_CRATE);
+		let crate_name = baseline_crate.as_deref()
+			.unwrap_or_else(|| module_path.split("::").next().unwrap());
+		let Some(depths) = compare_frames(frames, last_trace, history, basis_depth, anchor.as_deref(), crate_name) else {
+			return write_untraced(writer, text, module_path, last_time)
+		};
+		if !depths.is_in_window() {
+			is_suppressed = true;
+			return Ok(())
+		}
+		
+		 // Count Direct Recursion:
+		let text = match frames.split_last() {
+			Some((last, rest)) if show_recursion() => {
+				let count = 1 + rest.iter().rev()
+					.take_while(|frame| frame.name == last.name)
+					.count();
+				if count > 1 {
+					format!("↻{count} {text}")
+				} else {
+					text
+				}
+			},
+			_ => text,
+		};
+		
+		 // Name the Calling Function:
+		let text = match frames.last() {
+			Some(frame) if show_function() => format!("[{}] {text}", frame.display_name(symbol_style())),
+			_ => text,
+		};
+		
+		depth = Some(depths.indent());
+		write_indented(writer, depths, text, module_path, is_terminal, &mut state)
+	});
+	if result.is_ok() && !is_suppressed {
+		count_emitted(depth);
+	}
+	result
+}
+
+#[cfg(feature = "indent")]
+fn write_at<W>(
+	writer: &mut W,
+	depth: usize,
+	text: String,
+	module_path: &str,
+	is_terminal: bool,
+) -> io::Result<()>
+where
+	W: io::Write + ?Sized
+{
+	//! Writes a trace line indented to an explicit depth. See [`trace_at!`].
+	
+	let depths = Depths {
+		trace: depth,
+		matched: depth,
+		basis: 0,
+		is_rebased: false,
+	};
+	if !depths.is_in_window() {
+		return Ok(())
+	}
+	let result = STATE.with(|state| match state.try_borrow_mut() {
+		Ok(mut state) => write_indented(writer, depths, text, module_path, is_terminal, &mut state),
+		Err(_) => write_untraced(writer, text, module_path, &mut None),
+	});
+	if result.is_ok() {
+		count_emitted(Some(depths.indent()));
+	}
+	result
+}
+
+#[cfg(feature = "indent")]
+fn write_indented<W>(
+	writer: &mut W,
+	depths: Depths,
+	text: String,
+	module_path: &str,
+	is_terminal: bool,
+	state: &mut TraceState,
+) -> io::Result<()>
+where
+	W: io::Write + ?Sized
+{
+	//! Writes a trace line indented by the given depths, updating the state of
+	//! the last line printed.
+	
+	let TraceState { last_time, repeated_line, group_depth, .. } = state;
+	
+	let record_depths = Some([depths.trace, depths.matched, depths.basis]);
+	if let Some(line) = record_line(record_depths, module_path, &text) {
+		return write_line(writer, format_args!("{line}"))
+	}
+	
+	 // Print Line w/ Indentation:
+	let mut time = *last_time;
+	let prefix = line_prefix(&mut time);
+	let depth_text = if message_first() {
+		format!("{text} [d={} b={}]", depths.trace, depths.basis)
+	} else {
+		let config = config();
+		let (mut depth_text, continuations) = render_indent(
+			depths,
+			*group_depth,
+			module_offset(module_path),
+			&config,
+			is_colored(is_terminal),
+		);
+		let text = if escape_markers() {
+			escape_marker_heads(&text, &config)
+		} else {
+			text
+		};
+		let text = match wrap() {
+			Some(wrap) => {
+				let indent_width = prefix.chars().count() + continuations[1].chars().count();
+				wrap_text(&text.replace("\r\n", "\n"), wrap.saturating_sub(indent_width).max(1))
+			},
+			None => text,
+		};
+		depth_text += & if text.contains('\n') {
+			let prefix_indent = " ".repeat(prefix.chars().count());
+			let text = text.replace("\r\n", "\n");
+			let mut lines = text.split('\n');
+			let mut joined = lines.next().unwrap_or_default().to_owned();
+			let line_count = lines.clone().count();
+			for (index, line) in lines.enumerate() {
+				 // First, Middle, or Last Connector:
+				let continuation = if index + 1 == line_count {
+					&continuations[2]
+				} else if index == 0 {
+					&continuations[0]
+				} else {
+					&continuations[1]
+				};
+				joined += &format!("\n{prefix_indent}{continuation}{line}");
+			}
+			joined
+		} else {
+			text
+		};
+		
+		depth_text
+	};
+	
+	 // Collapse Repeated Lines:
+	if IS_WRITING_OUTPUT.get() {
+		if let Some(repeated) = repeated_line {
+			if collapse_repeats() && repeated.line == depth_text {
+				repeated.count += 1;
+				return Ok(())
+			}
+			repeated.write_summary(writer)?;
+		}
+		*repeated_line = collapse_repeats().then(|| RepeatedLine {
+			prefix: prefix.clone(),
+			line:   depth_text.clone(),
+			count:  1,
+		});
+	}
+	
+	*last_time = time;
+	write_line(writer, format_args!("{prefix}{depth_text}"))
+}
+
+#[cfg(feature = "indent")]
+fn write_raw<W>(
+	writer: &mut W,
+	frames: Option<&[Frame]>,
+	block: &str,
+	module_path: &str,
+	is_terminal: bool,
+) -> io::Result<()>
+where
+	W: io::Write + ?Sized
+{
+	//! Writes each line of a block indented like the continuation lines of a
+	//! trace by the given call stack frames. See [`indent_raw!`].
+	
+	 // Depth of Block:
+	let (depths, group_depth, mut time) = STATE.with(|state| {
+		let Ok(mut state) = state.try_borrow_mut() else {
+			return (None, 0, None)
+		};
+		if let Some(frames) = frames {
+			state.anchor_session(frames);
+		}
+		let TraceState { last_trace, history, basis_depth, last_time, group_depth, anchor, .. } = &mut *state;
+		let baseline_crate = read_lock(&BASELINE_CRATE);
+		let crate_name = baseline_crate.as_deref()
+			.unwrap_or_else(|| module_path.split("::").next().unwrap());
+		let depths = frames.and_then(|frames| {
+			compare_frames(frames, last_trace, history, basis_depth, anchor.as_deref(), crate_name)
+		});
+		(depths, *group_depth, *last_time)
+	});
+	
+	 // Print Lines w/ Indentation:
+	let record_depths = depths.map(|depths| [depths.trace, depths.matched, depths.basis]);
+	let prefix_indent = " ".repeat(line_prefix(&mut time).chars().count());
+	let continuation = depths.map_or(String::new(), |depths| {
+		let offset = module_offset(module_path);
+		let [_, continuation, _] = render_indent(depths, group_depth, offset, &config(), is_colored(is_terminal)).1;
+		continuation
+	});
+	let mut text = String::new();
+	for line in block.lines() {
+		if !text.is_empty() {
+			text.push('\n');
+		}
+		match record_line(record_depths, module_path, line) {
+			Some(record) => text += &record,
+			None => text += &format!("{prefix_indent}{continuation}{line}"),
+		}
+	}
+	if block.is_empty() {
+		return Ok(())
+	}
+	
+	write_line(writer, format_args!("{text}"))?;
+	count_emitted(depths.map(|depths| depths.indent()));
+	Ok(())
+}
+
+/// The depths of a trace within its call stack, counted from the bottom up.
+#[cfg(feature = "indent")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Depths {
+	/// The depth of the traced function.
+	trace: usize,
+	
+	/// The number of frames matching the previous trace's call stack.
+	matched: usize,
+	
+	/// The depth of the baseline, which traces are indented relative to.
+	basis: usize,
+	
+	/// Whether the baseline was moved by this trace.
+	is_rebased: bool,
+}
+
+#[cfg(feature = "indent")]
+impl Depths {
+	fn indent(self) -> usize {
+		//! Returns the number of levels a trace at these depths is indented by.
+		//! 
+		//! The depths of a trace keep `basis <= matched <= trace`, unless it's
+		//! rebased (where `matched` may be lower), but this saturates in case
+		//! that's ever broken, rather than panicking mid-trace.
+		
+		self.trace.saturating_sub(self.basis)
+	}
+	
+	fn is_in_window(self) -> bool {
+		//! Returns whether a trace at these depths should be printed. See
+		//! [`set_depth_window`].
+		
+		depth_window().is_none_or(|(min, max)| (min..=max).contains(&self.indent()))
+	}
+}
+
+#[cfg(feature = "indent")]
+fn compare_frames(
+	frames: &[Frame],
+	last_trace: &mut Vec<Frame>,
+	history: &mut VecDeque<Vec<Frame>>,
+	basis_depth: &mut usize,
+	anchor: Option<&[Frame]>,
+	crate_name: &str,
+) -> Option<Depths> {
+	//! Compares a call stack to the previous trace's, which it replaces, and
+	//! moves the baseline if they diverged before it, or to the anchor (see
+	//! [`trace_anchor!`]) if the call stack is within it, unless it's pinned
+	//! (see [`set_fixed_baseline`]). Returns `None` if there are no frames.
+	//! 
+	//! The call stacks in the history (see [`set_history`]) also count as
+	//! matching, and the previous trace's is added to it.
+	//! 
+	//! Only the common prefix of the two call stacks matches, so the frames of
+	//! a deeper previous call stack are never compared past the new one's end.
+	//! The trace's own frame isn't kept for the next comparison, since its
+	//! location is the trace's rather than that of a call.
+	
+	 // Best Match in History:
+	let history_size = self::history();
+	let history_depth = history.iter()
+		.map(|stack| stack.iter().zip(frames).take_while(|(a, b)| a == b).count())
+		.max()
+		.unwrap_or(0);
+	let previous_trace = (history_size > 0).then(|| last_trace.clone());
+	
+	let last_trace_depth = last_trace.len();
+	last_trace.reserve(frames.len().saturating_sub(last_trace.capacity()));
+	
+	let mut trace_depth = 0;
+	let mut match_depth = 0;
+	
+	 // Anchor to Bottom if Crate is Unnamed:
+	let mut crate_depth = if crate_name.trim().is_empty() {
+		Some(0)
+	} else {
+		None
+	};
+	
+	for frame in frames {
+		 // First Frame in Caller's Crate:
+		if crate_depth.is_none() && frame.is_in_crate(crate_name) {
+			crate_depth = Some(trace_depth);
+		}
+		
+		 // Compare & Update Stored Frame:
+		if trace_depth < last_trace_depth {
+			if match_depth == trace_depth && *frame == last_trace[trace_depth] {
+				match_depth += 1;
+			}
+			last_trace[trace_depth].clone_from(frame);
+		} else {
+			last_trace.push(frame.clone());
+		}
+		
+		trace_depth += 1;
+	}
+	if trace_depth == 0 {
+		return None
+	}
+	trace_depth -= 1;
+	
+	 // Drop Stale Frames & Trace's Own Frame:
+	last_trace.truncate(trace_depth);
+	match_depth = match_depth.max(history_depth).min(trace_depth);
+	
+	 // Remember Previous Trace:
+	if let Some(previous_trace) = previous_trace {
+		if previous_trace != *last_trace {
+			history.retain(|stack| *stack != previous_trace);
+			history.push_front(previous_trace);
+		}
+	}
+	history.truncate(history_size);
+	
+	 // Pin Baseline if Fixed:
+	if let Some(fixed_depth) = fixed_baseline() {
+		*basis_depth = fixed_depth;
+		return Some(Depths {
+			trace: trace_depth,
+			matched: match_depth,
+			basis: fixed_depth,
+			is_rebased: match_depth == 0 || match_depth < fixed_depth,
+		})
+	}
+	
+	 // Move Baseline to Anchor if Within It:
+	let anchor_depth = anchor.filter(|anchor| {
+		let Some((anchor_frame, outer_frames)) = anchor.split_last() else {
+			return false
+		};
+		frames.len() >= anchor.len()
+			&& frames[..outer_frames.len()] == *outer_frames
+			&& frames[outer_frames.len()].name == anchor_frame.name
+	}).map(|anchor| anchor.len() - 1);
+	let mut is_rebased = match_depth == 0 || match_depth < *basis_depth;
+	if let Some(anchor_depth) = anchor_depth {
+		is_rebased |= *basis_depth != anchor_depth || match_depth < anchor_depth;
+		*basis_depth = anchor_depth;
+	}
+	
+	 // Move Baseline if Diverged Before It:
+	else if is_rebased {
+		*basis_depth = crate_depth.unwrap_or(trace_depth);
+	}
+	
+	 // Move Baseline if Entered Caller's Crate Above It:
+	else if reanchor_on_crate_change() && !frames[*basis_depth].is_in_crate(crate_name) {
+		let boundary = frames[*basis_depth + 1..=trace_depth].iter()
+			.position(|frame| frame.is_in_crate(crate_name));
+		if let Some(offset) = boundary {
+			*basis_depth += 1 + offset;
+			is_rebased = true;
+		}
+	}
+	
+	Some(Depths {
+		trace: trace_depth,
+		matched: match_depth,
+		basis: *basis_depth,
+		is_rebased,
+	})
+}
+
+#[cfg(feature = "indent")]
+fn render_indent(
+	depths: Depths,
+	group_depth: usize,
+	module_offset: usize,
+	config: &TraceConfig,
+	is_colored: bool,
+) -> (String, [String; 3]) {
+	//! Returns the indentation of a trace's first line, and of its first,
+	//! middle, and last continuation lines if it's multiline. Each open group
+	//! (see [`trace_group!`]) is drawn as one more level before the trace's own
+	//! symbol, and each level of a module's offset (see [`set_module_offset`])
+	//! is blank after it.
+	
+	let Depths { trace: trace_depth, matched: match_depth, basis: basis_depth, is_rebased } = depths;
+	
+	 // Indentation Symbols:
+	let width = config.indent_width;
+	let (baseline_color, divergence_color, reset_color) = if is_colored {
+		("\x1b[36m", "\x1b[33m", "\x1b[0m")
+	} else {
+		("", "", "")
+	};
+	let baseline   = config.baseline.render(width);
+	let baseline   = format!("{baseline_color}{baseline}{reset_color}");
+	let gutter     = config.gutter.unwrap_or(width - 1);
+	let lone_basis = Symbol::new(config.baseline.head, config.current.fill).render(gutter + 1);
+	let lone_basis = format!("{baseline_color}{lone_basis}{reset_color}");
+	let diverged   = config.divergence.render(width);
+	let diverged   = format!("{divergence_color}{diverged}{reset_color}");
+	let matched    = config.matched.render(width);
+	let current    = config.current.render(gutter + 1);
+	
+	 // Symbol per Depth:
+	let indent_depth = depths.indent();
+	let mut symbols = Vec::with_capacity(indent_depth + 1);
+	if is_rebased {
+		if indent_depth == 0 {
+			symbols.push(lone_basis.as_str());
+		} else {
+			symbols.push(&baseline);
+			symbols.extend(std::iter::repeat_n(diverged.as_str(), indent_depth - 1));
+			symbols.push(&current);
+		}
+	} else {
+		symbols.extend(std::iter::repeat_n(matched.as_str(), match_depth.saturating_sub(basis_depth)));
+		symbols.extend(std::iter::repeat_n(diverged.as_str(), trace_depth.saturating_sub(match_depth)));
+		symbols.push(&current);
+	}
+	
+	 // Collapse Depths Past Maximum:
+	let max_depth = max_depth();
+	let ellipsis;
+	if indent_depth > max_depth {
+		let label = format!("…{indent_depth}");
+		ellipsis = if label.chars().count() <= width {
+			format!("{label:<width$}")
+		} else {
+			Symbol::new('…', ' ').render(width)
+		};
+		symbols.splice(..=(indent_depth - max_depth), [ellipsis.as_str()]);
+	}
+	
+	 // Indent Open Groups & Module Offsets:
+	let group_indent = config.current.render(width).repeat(group_depth);
+	let offset_indent = " ".repeat(width.saturating_mul(module_offset));
+	symbols.insert(symbols.len() - 1, &group_indent);
+	symbols.push(&offset_indent);
+	
+	 // Continuation Lines:
+	let continuation_indent = matched.repeat(indent_depth.min(max_depth));
+	let continuations = [config.first_continuation, config.continuation, config.last_continuation]
+		.map(|symbol| {
+			let symbol = symbol.map_or_else(|| current.clone(), |symbol| symbol.render(gutter + 1));
+			format!("{continuation_indent}{group_indent}{symbol}{offset_indent}")
+		});
+	
+	(symbols.concat(), continuations)
+}
+
+#[cfg(feature = "indent")]
+fn wrap_text(text: &str, width: usize) -> String {
+	//! Wraps each line of a message between words to fit within `width`
+	//! columns, splitting any word that doesn't fit on a line of its own.
+	
+	let mut wrapped = String::with_capacity(text.len());
+	for (index, line) in text.split('\n').enumerate() {
+		if index != 0 {
+			wrapped.push('\n');
+		}
+		let mut line_width = 0;
+		let mut has_word = false;
+		for word in line.split_inclusive(' ') {
+			 // Move Word to Next Line:
+			let word_width = word.trim_end_matches(' ').chars().count();
+			if has_word && line_width + word_width > width {
+				wrapped.truncate(wrapped.trim_end_matches(' ').len());
+				wrapped.push('\n');
+				line_width = 0;
+				has_word = false;
+			}
+			
+			 // Split Word if Too Long:
+			for c in word.chars() {
+				if c != ' ' {
+					if line_width >= width {
+						wrapped.push('\n');
+						line_width = 0;
+					}
+					has_word = true;
+				}
+				wrapped.push(c);
+				line_width += 1;
+			}
+		}
+	}
+	wrapped
+}
+
+#[cfg(feature = "indent")]
+fn escape_marker_heads(text: &str, config: &TraceConfig) -> String {
+	//! Escapes the heads of the indentation symbols in a message with a
+	//! backslash. See [`set_escape_markers`].
+	
+	let heads = [config.baseline.head, config.divergence.head, config.current.head, '\\'];
+	let mut escaped = String::with_capacity(text.len());
+	for c in text.chars() {
+		if heads.contains(&c) && !c.is_whitespace() {
+			escaped.push('\\');
+		}
+		escaped.push(c);
+	}
+	escaped
+}
+
+#[cfg(feature = "indent")]
+fn write_untraced<W>(
+	writer: &mut W,
+	text: String,
+	module_path: &str,
+	last_time: &mut Option<Instant>,
+) -> io::Result<()>
+where
+	W: io::Write + ?Sized
+{
+	//! Writes a trace line without indentation, for when there's no call stack.
+	
+	if let Some(line) = record_line(None, module_path, &text) {
+		return write_line(writer, format_args!("{line}"))
+	}
+	let marker = if mark_fallback() { "~ " } else { "" };
+	write_line(writer, format_args!("{}{marker}{text}", line_prefix(last_time)))
+}
+
+#[cfg(feature = "indent")]
+fn record_line(depths: Option<[usize; 3]>, module_path: &str, text: &str) -> Option<String> {
+	//! Returns a trace as formatted by [`set_formatter`], or as a JSON object if
+	//! enabled by [`set_json`]. Returns `None` if it should be indented instead.
+	//! The trace's structure is also kept for the sink, if it's written to one.
+	
+	 // Keep Structure for the Sink:
+	if IS_WRITING_SINK.get() {
+		SINK_RECORD.set(Some(SinkRecord {
+			depths,
+			module_path: module_path.to_owned(),
+			message: text.to_owned(),
+		}));
+	}
+	
+	let formatter = read_lock(&FORMATTER).clone();
+	if formatter.is_none() && !json() {
+		return None
+	}
+	
+	let thread = std::thread::current();
+	let record = TraceRecord {
+		depth:       depths.map(|depths| depths[0]),
+		match_depth: depths.map(|depths| depths[1]),
+		basis_depth: depths.map(|depths| depths[2]),
+		module_path,
+		thread:      thread.name(),
+		message:     text,
+	};
+	
+	Some(match formatter {
+		Some(formatter) => formatter(&record),
+		None => json_line(&record),
+	})
+}
+
+#[cfg(feature = "indent")]
+fn json_line(record: &TraceRecord) -> String {
+	//! Returns a trace as a JSON object. See [`set_json`].
+	
+	let depth = |depth: Option<usize>| depth
+		.map_or("null".to_owned(), |depth| depth.to_string());
+	
+	let thread = record.thread.map_or("null".to_owned(), json_string);
+	
+	format!(
+		concat!(
+			"{{\"depth\":{},\"match_depth\":{},\"basis_depth\":{},",
+			"\"module_path\":{},\"thread\":{},\"message\":{}}}",
+		),
+		depth(record.depth),
+		depth(record.match_depth),
+		depth(record.basis_depth),
+		json_string(record.module_path),
+		thread,
+		json_string(record.message),
+	)
+}
+
+#[cfg(feature = "indent")]
+fn json_string(text: &str) -> String {
+	//! Returns the text as a quoted and escaped JSON string.
+	
+	let mut string = String::with_capacity(text.len() + 2);
+	string.push('"');
+	for c in text.chars() {
+		match c {
+			'"'  => string += "\\\"",
+			'\\' => string += "\\\\",
+			'\n' => string += "\\n",
+			'\r' => string += "\\r",
+			'\t' => string += "\\t",
+			c if c.is_control() => string += &format!("\\u{:04x}", c as u32),
+			c => string.push(c),
+		}
+	}
+	string.push('"');
+	string
+}
+
+#[cfg(feature = "indent")]
+fn line_prefix(last_time: &mut Option<Instant>) -> String {
+	//! Returns the text printed left of the indentation, like a timestamp.
+	
+	let mut prefix = String::new();
+	if show_thread() {
+		let thread = std::thread::current();
+		let label = thread.name()
+			.map_or_else(|| format!("{:?}", thread.id()), str::to_owned);
+		prefix += &format!("{:<15} ", format!("[{label}]"));
+	}
+	if show_sequence() {
+		let sequence = SEQUENCE.fetch_add(1, Ordering::Relaxed);
+		prefix += &format!("#{sequence:06} ");
+	}
+	if timestamps() {
+		static START: OnceLock<Instant> = OnceLock::new();
+		let time = START.get_or_init(Instant::now).elapsed();
+		prefix += &format!("[{:>7.3}] ", time.as_secs_f64());
+	}
+	if show_deltas() {
+		let time = Instant::now();
+		let delta = last_time.replace(time)
+			.map_or(0., |last_time| (time - last_time).as_secs_f64() * 1000.);
+		prefix += &format!("{:<10} ", format!("+{delta:.2}ms"));
+	} else {
+		*last_time = None;
+	}
+	prefix
+}
+
+#[cfg(all(feature = "indent", feature = "color"))]
+fn is_colored(is_terminal: bool) -> bool {
+	//! Returns whether the indentation markers should be colored.
+	
+	match color_mode() {
+		ColorMode::Always => true,
+		ColorMode::Never  => false,
+		ColorMode::Auto   => {
+			 // https://no-color.org/
+			static NO_COLOR: OnceLock<bool> = OnceLock::new();
+			is_terminal && !*NO_COLOR.get_or_init(|| {
+				std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+			})
+		}
+	}
+}
+
+#[cfg(all(feature = "indent", not(feature = "color")))]
+fn is_colored(_is_terminal: bool) -> bool {
+	false
+}
+
+#[cfg(all(test, feature = "indent"))]
+mod tests {
+	use super::*;
+	
+	fn frames(names: &[&str]) -> Vec<Frame> {
+		names.iter()
+			.map(|name| Frame { name: (*name).to_owned(), location: None, mangled_name: None })
+			.collect()
+	}
+	
+	const fn depths(trace: usize, matched: usize, basis: usize, is_rebased: bool) -> Depths {
+		Depths { trace, matched, basis, is_rebased }
+	}
+	
+	 // Serializes Tests that Change Global Settings:
+	static SETTINGS: Mutex<()> = Mutex::new(());
+	
+	type CompareCase<'a> = (&'a [&'a str], Option<&'a [&'a str]>, Option<Depths>);
+	
+	fn compare_all(crate_name: &str, baseline: (Option<usize>, bool), cases: &[CompareCase]) {
+		let _settings = lock(&SETTINGS);
+		let (fixed_depth, is_reanchored) = baseline;
+		set_fixed_baseline(fixed_depth);
+		set_reanchor_on_crate_change(is_reanchored);
+		
+		let mut last_trace = Vec::new();
+		let mut history = VecDeque::new();
+		let mut basis_depth = 0;
+		for (index, (stack, anchor, expected)) in cases.iter().enumerate() {
+			let anchor = anchor.map(frames);
+			let depths = compare_frames(
+				&frames(stack),
+				&mut last_trace,
+				&mut history,
+				&mut basis_depth,
+				anchor.as_deref(),
+				crate_name,
+			);
+			assert_eq!(depths, *expected, "case {index}: {stack:?}, anchor {anchor:?}");
+		}
+		
+		set_fixed_baseline(None);
+		set_reanchor_on_crate_change(false);
+	}
+	
+	#[test]
+	fn compare_frames_branches() {
+		compare_all("app", (None, false), &[
+			 // First Trace Rebases to Caller's Crate:
+			(&["std::rt", "app::main", "app::a"], None, Some(depths(2, 0, 1, true))),
+			
+			 // Identical Call Stack:
+			(&["std::rt", "app::main", "app::a"], None, Some(depths(2, 2, 1, false))),
+			
+			 // Deeper Call:
+			(&["std::rt", "app::main", "app::a", "app::b"], None, Some(depths(3, 2, 1, false))),
+			
+			 // Diverged at the Bottom (`match_depth == 0`):
+			(&["other::rt", "app::main", "app::c"], None, Some(depths(2, 0, 1, true))),
+			
+			 // Diverged Above the Baseline:
+			(&["other::rt", "lib::run", "app::d"], None, Some(depths(2, 1, 1, false))),
+			
+			 // Diverged at the Bottom, Entering the Crate Higher Up:
+			(&["x::rt", "lib::run", "app::main", "app::e"], None, Some(depths(3, 0, 2, true))),
+			
+			 // Diverged Below the Baseline (`match_depth < basis_depth`):
+			(&["x::rt", "app::f"], None, Some(depths(1, 1, 1, true))),
+			
+			 // No Frames:
+			(&[], None, None),
+		]);
+		
+		let anchor: &[&str] = &["std::rt", "app::main", "app::run"];
+		compare_all("app", (None, false), &[
+			 // Within Anchor Rebases to It:
+			(&["std::rt", "app::main", "app::run", "app::a"], Some(anchor), Some(depths(3, 0, 2, true))),
+			
+			 // Still Within Anchor:
+			(&["std::rt", "app::main", "app::run", "app::a"], Some(anchor), Some(depths(3, 3, 2, false))),
+			
+			 // Diverged Outside Anchor:
+			(&["x::rt", "app::main", "app::b"], Some(anchor), Some(depths(2, 0, 1, true))),
+			
+			 // Anchor Deeper Than Call Stack:
+			(&["x::rt", "app::main"], Some(anchor), Some(depths(1, 1, 1, false))),
+		]);
+		
+		compare_all("app", (Some(1), false), &[
+			 // Fixed Baseline on First Trace:
+			(&["std::rt", "app::main", "app::a"], None, Some(depths(2, 0, 1, true))),
+			
+			 // Identical Call Stack:
+			(&["std::rt", "app::main", "app::a"], None, Some(depths(2, 2, 1, false))),
+			
+			 // Diverged at the Bottom, Ignoring Caller's Crate:
+			(&["x::rt", "lib::run", "app::main", "app::b"], None, Some(depths(3, 0, 1, true))),
+			
+			 // Ignoring Anchor:
+			(&["x::rt", "lib::run", "app::main", "app::c"], Some(&["x::rt", "lib::run", "app::main"]), Some(depths(3, 3, 1, false))),
+		]);
+		
+		compare_all("app", (None, true), &[
+			 // No Frame in Caller's Crate Rebases to the Trace:
+			(&["std::rt", "lib::run", "lib::a"], None, Some(depths(2, 0, 2, true))),
+			
+			 // Entered Caller's Crate Above the Baseline:
+			(&["std::rt", "lib::run", "lib::a", "app::b", "app::c"], None, Some(depths(4, 2, 3, true))),
+			
+			 // Baseline Already in Caller's Crate:
+			(&["std::rt", "lib::run", "lib::a", "app::b", "app::c", "app::d"], None, Some(depths(5, 4, 3, false))),
+		]);
+		
+		compare_all("app", (None, false), &[
+			 // No Frame in Caller's Crate Rebases to the Trace:
+			(&["std::rt", "lib::run", "lib::a"], None, Some(depths(2, 0, 2, true))),
+			
+			 // Entered Caller's Crate Above the Baseline, Not Reanchored:
+			(&["std::rt", "lib::run", "lib::a", "app::b", "app::c"], None, Some(depths(4, 2, 2, false))),
+		]);
+	}
+	
+	#[test]
+	fn compare_frames_unnamed_crate() {
+		for crate_name in ["", "  "] {
+			compare_all(crate_name, (None, false), &[
+				 // First Trace Rebases to the Bottom:
+				(&["std::rt", "app::main", "app::a"], None, Some(depths(2, 0, 0, true))),
+				
+				 // Deeper Call:
+				(&["std::rt", "app::main", "app::a", "app::b"], None, Some(depths(3, 2, 0, false))),
+				
+				 // Diverged at the Bottom:
+				(&["other::rt", "app::c"], None, Some(depths(1, 0, 0, true))),
+				
+				 // Diverged Above the Baseline:
+				(&["other::rt", "lib::run", "app::d"], None, Some(depths(2, 1, 0, false))),
+			]);
+		}
+	}
+	
+	#[cfg(target_os = "linux")]
+	fn write_syscalls() -> u64 {
+		std::fs::read_to_string("/proc/self/io").unwrap()
+			.lines()
+			.find_map(|line| line.strip_prefix("syscw: "))
+			.unwrap()
+			.parse()
+			.unwrap()
+	}
+	
+	#[cfg(target_os = "linux")]
+	#[test]
+	fn buffered_write_syscalls() {
+		 // Count Writes in a Child Process:
+		if let Ok(mode) = std::env::var("TRACE_SYSCALL_CHILD") {
+			set_buffered(mode == "buffered");
+			let before = write_syscalls();
+			for i in 0..1000 {
+				crate::trace!("i:{i}");
+			}
+			flush().unwrap();
+			eprintln!("syscw:{}", write_syscalls() - before);
+			return
+		}
+		
+		let count = |mode| {
+			let output = std::process::Command::new(std::env::current_exe().unwrap())
+				.args(["--exact", "tests::buffered_write_syscalls", "--nocapture"])
+				.env("TRACE_SYSCALL_CHILD", mode)
+				.output()
+				.unwrap();
+			String::from_utf8(output.stderr).unwrap()
+				.lines()
+				.find_map(|line| line.strip_prefix("syscw:")?.parse::<u64>().ok())
+				.unwrap()
+		};
+		let (buffered, unbuffered) = (count("buffered"), count("unbuffered"));
+		assert!(buffered * 10 < unbuffered, "{buffered} buffered writes, {unbuffered} unbuffered");
+	}
+	
+	#[cfg(target_os = "linux")]
+	#[test]
+	fn file_sink_keeps_first_error() {
+		let path = PathBuf::from("/dev/full");
+		let sink = FileSink {
+			file: Mutex::new(FileSink::open(&path).unwrap()),
+			path,
+			max_bytes: u64::MAX,
+			max_files: 0,
+			error: Mutex::new(None),
+		};
+		
+		 // Line Too Long to Buffer:
+		sink.write_line(&"x".repeat(10_000));
+		sink.write_line("y");
+		let error = TraceSink::flush(&sink).unwrap_err();
+		assert_eq!(error.kind(), io::ErrorKind::StorageFull);
+	}
+	
+	#[test]
+	fn render_indent_symbols() {
+		let _settings = lock(&SETTINGS);
+		let config = TraceConfig::new();
+		let cases = [
+			(depths(2, 0, 1, true),  0, 0, usize::MAX, "@---|   "),
+			(depths(1, 0, 1, true),  0, 0, usize::MAX, "@   "),
+			(depths(2, 2, 1, false), 0, 0, usize::MAX, "    |   "),
+			(depths(3, 2, 1, false), 0, 0, usize::MAX, "    >---|   "),
+			(depths(3, 1, 1, false), 0, 0, usize::MAX, ">--->---|   "),
+			(depths(3, 2, 1, false), 1, 0, usize::MAX, "    >---|   |   "),
+			(depths(3, 2, 1, false), 0, 1, usize::MAX, "    >---|       "),
+			
+			 // Collapsed Past Maximum Depth:
+			(depths(3, 1, 1, false), 0, 0, 1, "…2  |   "),
+			(depths(4, 1, 1, false), 0, 0, 2, "…3  >---|   "),
+			(depths(3, 0, 1, true),  0, 0, 1, "…2  |   "),
+			(depths(3, 1, 1, false), 1, 0, 1, "…2  |   |   "),
+			
+			 // Label Wider Than Indentation:
+			(depths(10_001, 10_000, 1, false), 0, 0, 1, "…   |   "),
+		];
+		for (depths, group_depth, module_offset, max_depth, expected) in cases {
+			set_max_depth(max_depth);
+			let (indent, _) = render_indent(depths, group_depth, module_offset, &config, false);
+			assert_eq!(
+				indent, expected,
+				"{depths:?}, group {group_depth}, offset {module_offset}, max depth {max_depth}",
+			);
+		}
+		set_max_depth(usize::MAX);
+	}
+	
+	#[test]
+	fn render_indent_continuations() {
+		let _settings = lock(&SETTINGS);
+		let config = TraceConfig::new();
+		let (_, continuations) = render_indent(depths(3, 2, 1, false), 1, 0, &config, false);
+		assert_eq!(continuations, ["        |   |   ", "        |   |   ", "        |   |   "]);
+	}
+	
+	#[test]
+	fn wrap_text_widths() {
+		let cases = [
+			("one two three", 7, "one two\nthree"),
+			("one two three", 8, "one two\nthree"),
+			("one two three", 13, "one two three"),
+			("abcdefghij", 4, "abcd\nefgh\nij"),
+			("ab abcdefgh", 4, "ab\nabcd\nefgh"),
+			("a\nb c", 1, "a\nb\nc"),
+			("", 4, ""),
+		];
+		for (text, width, expected) in cases {
+			assert_eq!(wrap_text(text, width), expected, "{text:?}, width {width}");
+		}
+	}
+	
+	#[test]
+	fn escape_marker_heads_symbols() {
+		let config = TraceConfig::new();
+		let cases = [
+			("@---|   a", "\\@---\\|   a"),
+			(">---|", "\\>---\\|"),
+			("a\\b", "a\\\\b"),
+			("a-b c", "a-b c"),
+		];
+		for (text, expected) in cases {
+			assert_eq!(escape_marker_heads(text, &config), expected, "{text:?}");
+		}
+	}
+	
+	#[test]
+	fn json_string_escapes() {
+		let cases = [
+			("abc", "\"abc\""),
+			("a\"b\\c", "\"a\\\"b\\\\c\""),
+			("\n\r\t", "\"\\n\\r\\t\""),
+			("\u{0}\u{1b}\u{7f}\u{85}", "\"\\u0000\\u001b\\u007f\\u0085\""),
+			("…", "\"…\""),
+		];
+		for (text, expected) in cases {
+			assert_eq!(json_string(text), expected, "{text:?}");
+		}
+	}
+}